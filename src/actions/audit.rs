@@ -0,0 +1,38 @@
+use crate::{
+  audit::Auditor,
+  config::McaiWorkersConfig,
+  error::Result,
+  index::Index,
+  report::{AuditReport, Format},
+};
+use clap::ArgMatches;
+use std::path::Path;
+
+/// Print the advisories of the packages locked by the workers, returns `false` when a worker is affected
+pub fn audit<'a>(cfg: &McaiWorkersConfig, matches: &ArgMatches<'a>) -> bool {
+  let format: Format = matches.value_of("format").unwrap().into();
+
+  let auditor = match auditor(matches) {
+    Ok(Some(auditor)) => auditor,
+    Ok(None) => unreachable!("the advisory database is required"),
+    Err(error) => {
+      eprintln!("Unable to audit the workers: {}", error);
+      return false;
+    }
+  };
+
+  let report = AuditReport::new(cfg, &auditor);
+  print!("{}", report.render(format));
+  !report.has_findings()
+}
+
+/// Open the advisory database given with `--advisory-db`, checking yanked versions when `--index` is given
+pub fn auditor<'a>(matches: &ArgMatches<'a>) -> Result<Option<Auditor>> {
+  let database = match matches.value_of("advisory-db") {
+    Some(database) => database,
+    None => return Ok(None),
+  };
+
+  let index = matches.value_of("index").map(Index::new).transpose()?;
+  Auditor::new(Path::new(database), index).map(Some)
+}
@@ -0,0 +1,91 @@
+use crate::{cache, config::McaiWorkersConfig};
+use clap::ArgMatches;
+use colored::Colorize;
+use console::Emoji;
+use indicatif::HumanBytes;
+use std::{fs, path::PathBuf};
+
+pub fn cache<'a>(cfg: &McaiWorkersConfig, matches: &ArgMatches<'a>) {
+  match matches.subcommand_name() {
+    Some("info") => info(cfg),
+    Some("clear") => clear(),
+    _ => unreachable!(),
+  }
+}
+
+fn info(cfg: &McaiWorkersConfig) {
+  println!("Cache folder: {}", cache::directory().display());
+
+  let mut registered = vec![];
+  for repo in &cfg.repos {
+    let directory = cache::repo_directory(repo);
+    let fetched_at = repo
+      .fetched
+      .files
+      .iter()
+      .filter_map(|file| file.fetched_at)
+      .max();
+
+    if directory.exists() {
+      println!(
+        "{} {}: {} file(s), {}{}",
+        Emoji("🗃️", "=>"),
+        repo.name.green().bold(),
+        repo.fetched.files.len(),
+        HumanBytes(cache::size(&directory)),
+        fetched_at
+          .map(|fetched_at| format!(", fetched {}", fetched_at.format("%Y-%m-%d %H:%M:%S UTC")))
+          .unwrap_or_default()
+      );
+    } else {
+      println!(
+        "{} {}: not fetched",
+        Emoji("🗃️", "=>"),
+        repo.name.yellow().bold()
+      );
+    }
+    registered.push(directory);
+  }
+
+  // caches of repositories which are not registered anymore
+  let unregistered: Vec<PathBuf> = fs::read_dir(cache::repos_directory())
+    .map(|entries| {
+      entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| !registered.contains(path))
+        .collect()
+    })
+    .unwrap_or_default();
+  if !unregistered.is_empty() {
+    println!(
+      "{} {} unregistered repository(ies): {}",
+      Emoji("🗑️", "=>"),
+      unregistered.len(),
+      HumanBytes(unregistered.iter().map(|path| cache::size(path)).sum())
+    );
+  }
+
+  println!(
+    "{} Git clones: {}",
+    Emoji("📦", "=>"),
+    HumanBytes(cache::size(&cache::git_directory()))
+  );
+  println!("Total: {}", HumanBytes(cache::size(&cache::directory())));
+}
+
+fn clear() {
+  let size = cache::size(&cache::directory());
+
+  match cache::clear() {
+    Ok(()) => println!(
+      "Removed cache folder: {} ({} freed)",
+      cache::directory().display(),
+      HumanBytes(size)
+    ),
+    Err(error) => {
+      println!("{} {}", "Unable to clear the cache:".red(), error);
+      std::process::exit(1);
+    }
+  }
+}
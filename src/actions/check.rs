@@ -0,0 +1,114 @@
+use crate::{
+  config::McaiWorkersConfig,
+  report::{Report, WorkerReport},
+  version::Lag,
+};
+use clap::ArgMatches;
+use colored::Colorize;
+use console::Emoji;
+
+/// Check the workers against the expected versions, printing the ones behind.
+/// Returns `false` when a worker lags at or above the failure threshold,
+/// or when a worker can not be checked, unless failures are disabled.
+pub fn check<'a>(cfg: &McaiWorkersConfig, matches: &ArgMatches<'a>) -> bool {
+  if cfg.rust_version.is_none() && cfg.mcai_sdk_version.is_none() {
+    println!("Warning: no expected version to check, set them with the `versions` command");
+    return true;
+  }
+
+  let warn = threshold(matches, "warn");
+  let fail = threshold(matches, "fail");
+  let reached =
+    |lag: Lag, threshold: Option<Lag>| threshold.is_some_and(|threshold| lag >= threshold);
+
+  let report = Report::new(cfg, false, None);
+  let mut failures = 0;
+  let mut warnings = 0;
+
+  for worker in &report.workers {
+    let violations: Vec<_> = worker
+      .violations()
+      .into_iter()
+      .filter(|violation| reached(violation.lag, warn) || reached(violation.lag, fail))
+      .collect();
+    let problems = problems(worker);
+    if violations.is_empty() && problems.is_empty() {
+      continue;
+    }
+
+    println!(
+      "{} {}",
+      Emoji("🚀", &"=>".green().bold()),
+      worker.name.green().bold()
+    );
+
+    for problem in problems {
+      if fail.is_some() {
+        failures += 1;
+        println!("  {} {}", Emoji("❌", "=>"), problem.red());
+      } else {
+        warnings += 1;
+        println!("  {} {}", Emoji("⚠️", "=>"), problem.yellow());
+      }
+    }
+
+    for violation in violations {
+      let message = format!(
+        "{}: {} ({} lag)",
+        violation.path,
+        violation.description,
+        violation.lag.as_str()
+      );
+      if reached(violation.lag, fail) {
+        failures += 1;
+        println!("  {} {}", Emoji("❌", "=>"), message.red());
+      } else {
+        warnings += 1;
+        println!("  {} {}", Emoji("⚠️", "=>"), message.yellow());
+      }
+    }
+  }
+
+  let expected: Vec<String> = [
+    ("MCAI Worker SDK", &cfg.mcai_sdk_version),
+    ("Rust", &cfg.rust_version),
+  ]
+  .iter()
+  .filter_map(|(name, version)| {
+    version
+      .as_ref()
+      .map(|version| format!("{} {}", name, version))
+  })
+  .collect();
+
+  println!(
+    "{} worker(s) checked against {}: {} failure(s), {} warning(s)",
+    report.workers.len(),
+    expected.join(", "),
+    failures,
+    warnings
+  );
+
+  failures == 0
+}
+
+/// Get the reasons why a worker can not be checked: a failed or missing fetch, or files which can not be parsed
+fn problems(worker: &WorkerReport) -> Vec<String> {
+  let mut problems = vec![];
+  if let Some(error) = &worker.last_error {
+    problems.push(format!("Last fetch failed: {}", error));
+  } else if worker.commit.is_none() {
+    problems.push("Never fetched".to_string());
+  }
+  for error in &worker.errors {
+    problems.push(format!("{}: {}", error.path, error.error));
+  }
+  problems
+}
+
+fn threshold<'a>(matches: &ArgMatches<'a>, name: &str) -> Option<Lag> {
+  match matches.value_of(name).unwrap() {
+    "never" => None,
+    lag => Some(lag.into()),
+  }
+}
@@ -0,0 +1,12 @@
+use crate::{
+  config::McaiWorkersConfig,
+  report::{DriftReport, Format},
+};
+use clap::ArgMatches;
+
+pub fn drift<'a>(cfg: &McaiWorkersConfig, matches: &ArgMatches<'a>) {
+  let format: Format = matches.value_of("format").unwrap().into();
+
+  let report = DriftReport::new(cfg, matches.is_present("all"));
+  print!("{}", report.render(format));
+}
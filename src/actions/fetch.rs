@@ -1,60 +1,660 @@
 use crate::{
-  config::{McaiWorkersConfig, Provider},
-  github::Github,
-  gitlab::Gitlab,
+  cargo,
+  config::{FetchedFile, FileKind, FileStatus, McaiWorkersConfig, RepoConfig},
+  error::{Error, Result},
+  http, parallel,
+  provider::{self, FileRequest, FileResponse, ResolveRequest, Resolved, SourceProvider, TreeFile},
 };
-use clap::ArgMatches;
-
-pub fn fetch<'a>(cfg: &mut McaiWorkersConfig, matches: &ArgMatches<'a>) {
-  for repo in cfg.repos.iter_mut() {
-    println!("Fetch {}", repo.name);
-    match repo.provider {
-      Provider::Github => {
-        let token = matches.value_of("github-token").unwrap();
-        let github = Github::new(token);
-
-        let manifest_filenames = if repo.manifest_filenames.is_empty() {
-          vec!["Cargo.toml".to_string()]
-        } else {
-          repo.manifest_filenames.clone()
-        };
-
-        repo.manifest_contents.clear();
-        for manifest_filename in manifest_filenames {
-          if let Some(cargo_toml_manifest) = github.get_file_content(&repo.name, &manifest_filename)
-          {
-            repo.manifest_contents.push(cargo_toml_manifest);
-          }
+use chrono::{DateTime, Utc};
+use clap::{value_t, ArgMatches};
+use colored::Colorize;
+use console::Emoji;
+use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
+use indicatif::{ProgressBar, ProgressStyle};
+use std::collections::BTreeMap;
+
+// files requested in a single batch, when enabled
+static BATCH_SIZE: usize = 50;
+// repositories resolved in a single batch, when enabled
+static RESOLVE_BATCH_SIZE: usize = 20;
+
+// kind, path and blob SHA (when known from the tree) of a file to fetch
+type RequestedFile = (FileKind, String, Option<String>);
+
+struct Resolution {
+  reference: String,
+  commit: String,
+  // files of the repository at the commit, when the provider can list them
+  tree: Option<Vec<TreeFile>>,
+  files: Vec<RequestedFile>,
+}
+
+struct FileTask<'a> {
+  index: usize,
+  commit: &'a str,
+  kind: FileKind,
+  path: &'a str,
+  tree_sha: Option<&'a str>,
+  previous: Option<&'a FetchedFile>,
+}
+
+impl<'a> FileTask<'a> {
+  fn new(
+    index: usize,
+    repo: &'a RepoConfig,
+    resolution: &'a Resolution,
+    (kind, path, tree_sha): &'a RequestedFile,
+  ) -> Self {
+    FileTask {
+      index,
+      commit: &resolution.commit,
+      kind: *kind,
+      path,
+      tree_sha: tree_sha.as_deref(),
+      previous: repo.files(*kind).find(|file| &file.path == path),
+    }
+  }
+}
+
+enum Outcome {
+  Unchanged(FetchedFile),
+  Updated(FetchedFile),
+  Missing,
+}
+
+/// Files fetched for each repository, with the count of changed files and the first error
+struct Fetched {
+  fetched_at: DateTime<Utc>,
+  files: Vec<Vec<FetchedFile>>,
+  changes: Vec<usize>,
+  errors: Vec<Option<String>>,
+}
+
+impl Fetched {
+  fn new(count: usize) -> Self {
+    Fetched {
+      fetched_at: Utc::now(),
+      files: vec![vec![]; count],
+      changes: vec![0; count],
+      errors: vec![None; count],
+    }
+  }
+
+  fn add(&mut self, tasks: &[FileTask], outcomes: Vec<Result<Outcome>>) {
+    for (task, outcome) in tasks.iter().zip(outcomes) {
+      let mut record = |mut file: FetchedFile, status| {
+        file.status = status;
+        file.commit = Some(task.commit.to_string());
+        file.fetched_at = Some(self.fetched_at);
+        self.files[task.index].push(file);
+      };
+
+      match outcome {
+        Ok(Outcome::Unchanged(file)) => record(file, FileStatus::Unchanged),
+        Ok(Outcome::Updated(file)) => {
+          record(file, FileStatus::Updated);
+          self.changes[task.index] += 1;
         }
+        Ok(Outcome::Missing) => {}
+        Err(error) => {
+          self.errors[task.index].get_or_insert_with(|| format!("{}: {}", task.path, error));
+        }
+      }
+    }
+  }
+}
+
+/// Fetch the files of all repositories, returning whether all of them were fetched.
+/// The previous contents of a failing repository are kept, next to its error.
+pub fn fetch<'a>(cfg: &mut McaiWorkersConfig, matches: &ArgMatches<'a>) -> bool {
+  let jobs = value_t!(matches, "jobs", usize).unwrap_or_else(|error| error.exit());
+  let batch = matches.is_present("batch");
+
+  let providers: Vec<Result<Box<dyn SourceProvider>>> = cfg
+    .repos
+    .iter()
+    .map(|repo| provider::new(cfg, repo, matches))
+    .collect();
+
+  let progress = ProgressBar::new(cfg.repos.len() as u64);
+  progress.set_style(
+    ProgressStyle::default_bar()
+      .template("{spinner} [{bar:40}] {pos}/{len} {wide_msg}")
+      .progress_chars("=> "),
+  );
+
+  let repositories: Vec<(&RepoConfig, Option<&dyn SourceProvider>)> = cfg
+    .repos
+    .iter()
+    .zip(
+      providers
+        .iter()
+        .map(|provider| provider.as_ref().ok().map(|provider| provider.as_ref())),
+    )
+    .collect();
+
+  // repositories without provider are not resolved, the provider error is reported instead
+  let resolutions: Vec<Option<Result<Resolution>>> =
+    resolve_repositories(&repositories, jobs, batch, &progress)
+      .into_iter()
+      .zip(&repositories)
+      .map(|(resolved, (repo, _))| {
+        resolved.map(|resolved| resolved.and_then(|resolved| resolution(repo, resolved)))
+      })
+      .collect();
+
+  let resolved = || {
+    resolutions
+      .iter()
+      .enumerate()
+      .filter_map(|(index, resolution)| match resolution {
+        Some(Ok(resolution)) => Some((index, resolution)),
+        _ => None,
+      })
+  };
 
-        let docker_filenames = if repo.docker_filenames.is_empty() {
-          vec!["Dockerfile".to_string()]
-        } else {
-          repo.docker_filenames.clone()
-        };
+  let tasks: Vec<FileTask> = resolved()
+    .flat_map(|(index, resolution)| {
+      let repo = repositories[index].0;
 
-        repo.docker_contents.clear();
-        for docker_filename in docker_filenames {
-          if let Some(dockerfile) = github.get_file_content(&repo.name, &docker_filename) {
-            repo.docker_contents.push(dockerfile);
+      resolution
+        .files
+        .iter()
+        .map(move |file| FileTask::new(index, repo, resolution, file))
+    })
+    .collect();
+
+  let mut fetched = Fetched::new(cfg.repos.len());
+  fetched.add(
+    &tasks,
+    fetch_files(&repositories, &tasks, jobs, batch, &progress),
+  );
+
+  // manifests (and lockfiles) of workspace members which are not matched by the patterns of the repository
+  let mut members: Vec<(usize, RequestedFile)> = resolved()
+    .flat_map(|(index, resolution)| {
+      let manifests: Vec<&FetchedFile> = fetched.files[index]
+        .iter()
+        .filter(|file| file.kind == FileKind::Manifest)
+        .collect();
+
+      let mut members: Vec<RequestedFile> =
+        cargo::workspace_members(&manifests, resolution.tree.as_deref())
+          .into_iter()
+          .map(|(path, sha)| (FileKind::Manifest, path, sha))
+          .collect();
+      members.extend(lockfiles(&members, resolution.tree.as_deref()));
+
+      members
+        .into_iter()
+        .filter(move |(kind, path, _)| {
+          !resolution
+            .files
+            .iter()
+            .any(|(requested_kind, requested, _)| requested_kind == kind && requested == path)
+        })
+        .map(move |file| (index, file))
+    })
+    .collect();
+  members.sort();
+  members.dedup();
+
+  let member_tasks: Vec<FileTask> = members
+    .iter()
+    .filter_map(|(index, file)| match &resolutions[*index] {
+      Some(Ok(resolution)) => Some(FileTask::new(
+        *index,
+        repositories[*index].0,
+        resolution,
+        file,
+      )),
+      _ => None,
+    })
+    .collect();
+
+  fetched.add(
+    &member_tasks,
+    fetch_files(&repositories, &member_tasks, jobs, batch, &progress),
+  );
+
+  progress.finish_and_clear();
+
+  let mut failures = vec![];
+  // files of failed repositories are not stored, so their changes are not counted
+  let mut changed = 0;
+  for (repo, (((provider, resolution), (files, changes)), file_error)) in cfg.repos.iter_mut().zip(
+    providers
+      .into_iter()
+      .zip(resolutions)
+      .zip(fetched.files.into_iter().zip(fetched.changes.iter()))
+      .zip(fetched.errors),
+  ) {
+    let resolution = match (provider, resolution) {
+      (Err(error), _) | (Ok(_), Some(Err(error))) => Err(error.to_string()),
+      (Ok(_), Some(Ok(resolution))) => match file_error {
+        Some(error) => Err(error),
+        None => Ok(resolution),
+      },
+      (Ok(_), None) => unreachable!(),
+    };
+
+    let resolution = match resolution {
+      Ok(resolution) => resolution,
+      Err(error) => {
+        println!("{} {}: {}", Emoji("❌", "x"), repo.name.red().bold(), error);
+        repo.fetched.last_error = Some(error.clone());
+        failures.push((repo.name.clone(), error));
+        continue;
+      }
+    };
+
+    repo.fetched.files = files;
+    repo.fetched.last_error = None;
+
+    let count = |kind| {
+      repo
+        .fetched
+        .files
+        .iter()
+        .filter(|file| file.kind == kind)
+        .count()
+    };
+    println!(
+      "{} {} {} @ {}: {} manifest(s), {} lockfile(s), {} Python file(s), {} toolchain file(s), {} Dockerfile(s), {} changed",
+      Emoji("✅", "=>"),
+      repo.name.green().bold(),
+      resolution.reference,
+      resolution.commit,
+      count(FileKind::Manifest),
+      count(FileKind::Lockfile),
+      count(FileKind::Python),
+      count(FileKind::Toolchain),
+      count(FileKind::Dockerfile),
+      changes
+    );
+
+    repo.fetched.fetched_reference = Some(resolution.reference);
+    repo.fetched.commit = Some(resolution.commit);
+    changed += changes;
+  }
+
+  println!("{} file(s) changed since the last fetch", changed);
+
+  print_rate_limits();
+
+  if !failures.is_empty() {
+    print_failures(&failures);
+  }
+
+  cfg.store();
+  failures.is_empty()
+}
+
+/// Print the remaining API quota of each host queried during the fetch
+fn print_rate_limits() {
+  for (host, rate_limit) in http::rate_limits() {
+    let remaining = match rate_limit.limit {
+      Some(limit) => format!("{}/{}", rate_limit.remaining, limit),
+      None => rate_limit.remaining.to_string(),
+    };
+    let reset = rate_limit
+      .reset
+      .map(|reset| format!(", reset at {}", reset.format("%H:%M:%S UTC")))
+      .unwrap_or_default();
+
+    println!(
+      "{} {}: {} API request(s) remaining{}",
+      Emoji("⏳", "=>"),
+      host,
+      remaining,
+      reset
+    );
+  }
+}
+
+/// Fetch files in parallel, advancing the progress bar.
+/// With `batch`, files of providers supporting it are requested together, `BATCH_SIZE` files at a time.
+fn fetch_files(
+  repositories: &[(&RepoConfig, Option<&dyn SourceProvider>)],
+  tasks: &[FileTask],
+  jobs: usize,
+  batch: bool,
+  progress: &ProgressBar,
+) -> Vec<Result<Outcome>> {
+  progress.inc_length(tasks.len() as u64);
+
+  let mut outcomes: Vec<Option<Result<Outcome>>> = (0..tasks.len()).map(|_| None).collect();
+
+  if batch {
+    let mut batches: BTreeMap<String, Vec<usize>> = BTreeMap::new();
+    for (index, task) in tasks.iter().enumerate() {
+      let key = repositories[task.index]
+        .1
+        .and_then(|provider| provider.batch_key());
+      if let Some(key) = key {
+        match unchanged(task) {
+          Some(outcome) => {
+            outcomes[index] = Some(Ok(outcome));
+            progress.inc(1);
           }
+          None => batches.entry(key).or_default().push(index),
         }
       }
-      Provider::Gitlab => {
-        let token = matches.value_of("gitlab-token").unwrap();
-        let gitlab = Gitlab::new(token);
+    }
+
+    let chunks: Vec<&[usize]> = batches
+      .values()
+      .flat_map(|indexes| indexes.chunks(BATCH_SIZE))
+      .collect();
 
-        if let Some(cargo_toml_manifest) = gitlab.get_file_content(&repo.name, "Cargo.toml") {
-          repo.manifest_contents.push(cargo_toml_manifest);
+    let responses = parallel::map(&chunks, jobs, |chunk| {
+      // providers of the same batch are equivalent, the first one is used
+      let provider = repositories[tasks[chunk[0]].index].1.unwrap();
+      let requests: Vec<FileRequest> = chunk
+        .iter()
+        .map(|index| {
+          let task = &tasks[*index];
+          FileRequest {
+            repository: &repositories[task.index].0.name,
+            reference: task.commit,
+            path: task.path,
+          }
+        })
+        .collect();
+
+      progress.set_message(&format!("Fetch a batch of {} file(s)", requests.len()));
+      let responses = provider.get_files(&requests);
+      progress.inc(chunk.len() as u64);
+      responses
+    });
+
+    for (chunk, responses) in chunks.iter().zip(responses) {
+      match responses {
+        Ok(responses) => {
+          for (index, response) in chunk.iter().zip(responses) {
+            outcomes[*index] = Some(response.map(|response| outcome(&tasks[*index], response)));
+          }
+        }
+        Err(error) => {
+          let message = error.to_string();
+          for index in chunk.iter() {
+            outcomes[*index] = Some(Err(Error::Api(message.clone())));
+          }
         }
+      }
+    }
+  }
+
+  // files which are not batched are requested one by one
+  let remaining: Vec<usize> = (0..tasks.len())
+    .filter(|index| outcomes[*index].is_none())
+    .collect();
+
+  let remaining_outcomes = parallel::map(&remaining, jobs, |index| {
+    let task = &tasks[*index];
+    let (repo, provider) = repositories[task.index];
+    progress.set_message(&format!("Fetch {}/{}", repo.name, task.path));
+
+    let outcome = provider
+      .map(|provider| fetch_file(repo, provider, task))
+      .unwrap_or(Ok(Outcome::Missing));
+    progress.inc(1);
+    outcome
+  });
+
+  for (index, outcome) in remaining.into_iter().zip(remaining_outcomes) {
+    outcomes[index] = Some(outcome);
+  }
+
+  outcomes
+    .into_iter()
+    .map(|outcome| outcome.unwrap())
+    .collect()
+}
+
+/// Print the repositories which failed to be fetched, with their error
+fn print_failures(failures: &[(String, String)]) {
+  let width = failures
+    .iter()
+    .map(|(name, _)| name.len())
+    .max()
+    .unwrap_or_default()
+    .max("Repository".len());
 
-        repo.docker_contents.clear();
-        if let Some(dockerfile) = gitlab.get_file_content(&repo.name, "Dockerfile") {
-          repo.docker_contents.push(dockerfile);
+  println!();
+  println!(
+    "{} {} repository(ies) failed to be fetched, previous contents are kept:",
+    Emoji("⚠️", "!"),
+    failures.len()
+  );
+  println!("  {:width$}  Error", "Repository", width = width);
+  for (name, error) in failures {
+    println!("  {:width$}  {}", name, error, width = width);
+  }
+}
+
+/// Resolve the commits to fetch in parallel, advancing the progress bar, repositories without provider giving `None`.
+/// With `batch`, repositories of providers supporting it are resolved together, `RESOLVE_BATCH_SIZE` at a time,
+/// those of a failing batch being resolved one by one.
+fn resolve_repositories(
+  repositories: &[(&RepoConfig, Option<&dyn SourceProvider>)],
+  jobs: usize,
+  batch: bool,
+  progress: &ProgressBar,
+) -> Vec<Option<Result<Resolved>>> {
+  let mut batched: Vec<Option<Result<Resolved>>> = (0..repositories.len()).map(|_| None).collect();
+
+  if batch {
+    let mut batches: BTreeMap<String, Vec<usize>> = BTreeMap::new();
+    for (index, (_, provider)) in repositories.iter().enumerate() {
+      if let Some(key) = provider.and_then(|provider| provider.batch_key()) {
+        batches.entry(key).or_default().push(index);
+      }
+    }
+
+    let chunks: Vec<&[usize]> = batches
+      .values()
+      .flat_map(|indexes| indexes.chunks(RESOLVE_BATCH_SIZE))
+      .collect();
+
+    let responses = parallel::map(&chunks, jobs, |chunk| {
+      // providers of the same batch are equivalent, the first one is used
+      let provider = repositories[chunk[0]].1.unwrap();
+      let requests: Vec<ResolveRequest> = chunk
+        .iter()
+        .map(|index| {
+          let repo = repositories[*index].0;
+          ResolveRequest {
+            repository: &repo.name,
+            reference: repo.reference.as_deref(),
+          }
+        })
+        .collect();
+
+      progress.set_message(&format!(
+        "Resolve a batch of {} repositories",
+        requests.len()
+      ));
+      let responses = provider.resolve_all(&requests);
+      if responses.is_ok() {
+        progress.inc(chunk.len() as u64);
+      }
+      responses
+    });
+
+    for (chunk, responses) in chunks.iter().zip(responses) {
+      if let Ok(responses) = responses {
+        for (index, response) in chunk.iter().zip(responses) {
+          batched[*index] = Some(response);
         }
       }
     }
   }
 
-  cfg.store();
+  // repositories which are not batched are resolved one by one
+  let remaining: Vec<usize> = (0..repositories.len())
+    .filter(|index| batched[*index].is_none())
+    .collect();
+
+  let mut remaining_resolutions = parallel::map(&remaining, jobs, |index| {
+    let (repo, provider) = repositories[*index];
+    progress.set_message(&format!("Resolve {}", repo.name));
+    let resolved = provider.map(|provider| provider.resolve(&repo.name, repo.reference.as_deref()));
+    progress.inc(1);
+    resolved
+  })
+  .into_iter();
+
+  batched
+    .into_iter()
+    .map(|resolved| match resolved {
+      Some(resolved) => Some(resolved),
+      None => remaining_resolutions.next().unwrap(),
+    })
+    .collect()
+}
+
+/// Get the files to fetch at the resolved commit of a repository
+fn resolution(repo: &RepoConfig, resolved: Resolved) -> Result<Resolution> {
+  let Resolved {
+    reference,
+    commit,
+    tree,
+  } = resolved;
+
+  let no_filenames = vec![];
+  let kinds = [
+    (
+      FileKind::Manifest,
+      &repo.manifest_filenames,
+      repo.manifest_paths(),
+      repo.manifest_patterns(),
+    ),
+    (
+      FileKind::Dockerfile,
+      &repo.docker_filenames,
+      repo.docker_paths(),
+      repo.docker_patterns(),
+    ),
+    (
+      FileKind::Python,
+      &no_filenames,
+      repo.python_paths(),
+      repo.python_patterns(),
+    ),
+    (
+      FileKind::Toolchain,
+      &no_filenames,
+      repo.toolchain_paths(),
+      repo.toolchain_patterns(),
+    ),
+  ];
+
+  let mut files = vec![];
+  for (kind, filenames, paths, patterns) in kinds.iter() {
+    match &tree {
+      // request the configured files and those matching the patterns, when the tree is available
+      Some(tree) => {
+        let patterns = glob_set(patterns)?;
+
+        files.extend(
+          tree
+            .iter()
+            .filter(|file| filenames.contains(&file.path) || patterns.is_match(&file.path))
+            .map(|file| (*kind, file.path.clone(), file.sha.clone())),
+        );
+      }
+      None => files.extend(paths.iter().map(|path| (*kind, path.clone(), None))),
+    }
+  }
+  files.extend(lockfiles(&files, tree.as_deref()));
+
+  Ok(Resolution {
+    reference,
+    commit,
+    tree,
+    files,
+  })
+}
+
+/// Get the lockfiles next to the requested manifests, those present in the tree when it is available
+fn lockfiles(files: &[RequestedFile], tree: Option<&[TreeFile]>) -> Vec<RequestedFile> {
+  files
+    .iter()
+    .filter(|(kind, _, _)| *kind == FileKind::Manifest)
+    .filter_map(|(_, path, _)| {
+      let path = cargo::join(cargo::parent_directory(path), cargo::LOCKFILE_FILENAME);
+
+      match tree {
+        Some(tree) => tree
+          .iter()
+          .find(|file| file.path == path)
+          .map(|file| (FileKind::Lockfile, path, file.sha.clone())),
+        None => Some((FileKind::Lockfile, path, None)),
+      }
+    })
+    .collect()
+}
+
+fn glob_set(patterns: &[String]) -> Result<GlobSet> {
+  let mut builder = GlobSetBuilder::new();
+  for pattern in patterns {
+    builder.add(GlobBuilder::new(pattern).literal_separator(true).build()?);
+  }
+
+  Ok(builder.build()?)
+}
+
+/// Fetch a file, unless its blob SHA or entity tag shows it did not change since the last fetch
+fn fetch_file(
+  repo: &RepoConfig,
+  provider: &dyn SourceProvider,
+  task: &FileTask,
+) -> Result<Outcome> {
+  if let Some(outcome) = unchanged(task) {
+    return Ok(outcome);
+  }
+
+  let etag = task.previous.and_then(|previous| previous.etag.as_deref());
+  let response = provider.get_file(&repo.name, task.commit, task.path, etag)?;
+  Ok(outcome(task, response))
+}
+
+/// Get the previously fetched file when its blob SHA in the tree did not change
+fn unchanged(task: &FileTask) -> Option<Outcome> {
+  let previous = task.previous?;
+  if task.tree_sha.is_some() && task.tree_sha == previous.sha.as_deref() {
+    Some(Outcome::Unchanged(previous.clone()))
+  } else {
+    None
+  }
+}
+
+fn outcome(task: &FileTask, response: Option<FileResponse>) -> Outcome {
+  match response {
+    Some(FileResponse::NotModified) => match task.previous {
+      Some(previous) => Outcome::Unchanged(previous.clone()),
+      None => Outcome::Missing,
+    },
+    Some(FileResponse::Content(content)) => {
+      let file = FetchedFile {
+        path: task.path.to_string(),
+        kind: task.kind,
+        status: FileStatus::Updated,
+        commit: Some(task.commit.to_string()),
+        fetched_at: None,
+        sha: content
+          .sha
+          .or_else(|| task.tree_sha.map(|sha| sha.to_string())),
+        etag: content.etag,
+        content: content.content,
+      };
+
+      match task.previous {
+        Some(previous) if previous.content == file.content => Outcome::Unchanged(file),
+        _ => Outcome::Updated(file),
+      }
+    }
+    None => Outcome::Missing,
+  }
 }
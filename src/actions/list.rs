@@ -1,150 +1,23 @@
-use crate::config::McaiWorkersConfig;
-use cargo_toml::Manifest;
+use super::audit::auditor;
+use crate::{
+  config::McaiWorkersConfig,
+  report::{Format, Report},
+};
 use clap::ArgMatches;
-use colored::Colorize;
-use console::Emoji;
-use dockerfile_parser::Dockerfile;
-use semver::VersionReq;
 
-pub fn list<'a>(cfg: &McaiWorkersConfig, matches: &ArgMatches<'a>) {
-  for repo in &cfg.repos {
-    println!();
-    println!(
-      "{} {}",
-      Emoji("🚀", &"=>".green().bold()),
-      repo.name.green().bold()
-    );
+/// Print the report of the workers, returns `false` when the advisory database can not be opened
+pub fn list<'a>(cfg: &McaiWorkersConfig, matches: &ArgMatches<'a>) -> bool {
+  let format: Format = matches.value_of("format").unwrap().into();
 
-    for manifest_content in &repo.manifest_contents {
-      let manifest = Manifest::from_str(&manifest_content).unwrap();
-
-      if let Some(package) = &manifest.package {
-        println!(
-          "  {} {} {} {}{}",
-          Emoji("📙", &"=>".magenta().bold()),
-          "Rust project".yellow(),
-          package.name.yellow(),
-          "v".yellow(),
-          package.version.yellow()
-        );
-      }
-
-      if let Some(version) = mcai_worker_sdk_version(&manifest) {
-        let extra = cfg
-          .mcai_sdk_version
-          .as_ref()
-          .map(|mcai_sdk_version| {
-            VersionReq::parse(&version)
-              .map(|version| {
-                if !version.matches(&mcai_sdk_version) {
-                  Some(format!(
-                    "{} Update required to version {}",
-                    Emoji("❗", "=>"),
-                    mcai_sdk_version
-                  ))
-                } else {
-                  None
-                }
-              })
-              .ok()
-              .unwrap_or_default()
-          })
-          .unwrap_or_default();
-
-        println!(
-          "    {} {} {} {}",
-          Emoji("📦", &"=>".magenta().bold()),
-          "MCAI Worker SDK".magenta(),
-          version.magenta(),
-          extra.unwrap_or_else(|| "".to_string()).red()
-        );
-      }
-    }
-
-    for dockerfile in &repo.docker_contents {
-      let dockerfile = Dockerfile::parse(&dockerfile).unwrap();
-
-      if let Some(image) = docker_information(&dockerfile) {
-        println!("  {} {}", Emoji("🐳", &"=>".cyan().bold()), image.cyan());
-      }
-    }
-
-    if matches.is_present("dependencies") {
-      for cargo_content in &repo.manifest_contents {
-        let manifest = Manifest::from_str(&cargo_content).unwrap();
-        for (name, version) in &manifest.dependencies {
-          let version = match version {
-            cargo_toml::Dependency::Simple(version) => version.to_string(),
-            version => format!("{:?}", version),
-          };
-
-          println!("  - {} ({})", name, version);
-        }
-      }
+  let auditor = match auditor(matches) {
+    Ok(auditor) => auditor,
+    Err(error) => {
+      eprintln!("Unable to audit the workers: {}", error);
+      return false;
     }
-  }
-}
-
-fn mcai_worker_sdk_version(manifest: &Manifest) -> Option<String> {
-  for (name, version) in &manifest.dependencies {
-    if name == "mcai_worker_sdk" {
-      return match version {
-        cargo_toml::Dependency::Simple(version) => Some(version.to_string()),
-        cargo_toml::Dependency::Detailed(detailed) => {
-          if let Some(version) = &detailed.version {
-            Some(version.to_string())
-          } else {
-            Some(detailed.path.clone().unwrap_or_else(|| "".to_string()))
-          }
-        }
-      };
-    }
-  }
-  None
-}
-
-fn docker_information(dockerfile: &Dockerfile) -> Option<String> {
-  use dockerfile_parser::{ImageRef, Instruction};
-
-  let images = dockerfile
-    .instructions
-    .iter()
-    .filter(|instruction| matches!(instruction, Instruction::From(_)))
-    .map(|instruction| {
-      if let Instruction::From(content) = instruction {
-        content.image_parsed.clone()
-      } else {
-        unreachable!()
-      }
-    })
-    .collect::<Vec<ImageRef>>();
-
-  if images.is_empty() {
-    return None;
-  }
-
-  let images = images
-    .iter()
-    .map(|image| {
-      let reference_image = match image.image.as_str() {
-        "rust" => "Rust",
-        "ubuntu" => "Ubuntu",
-        "debian" => "Debian",
-        "mediacloudai/rs_command_line_worker" => "Command Line",
-        "mediacloudai/py_mcai_worker_sdk" => "Python MCAI SDK",
-        "mediacloudai/c_mcai_worker_sdk" => "C MCAI SDK",
-        "mediacloudai/docker_alpine_ffmpeg" => "Alpine FFmpeg",
-        image_name => image_name,
-      };
-
-      format!(
-        "{} {}",
-        reference_image,
-        image.tag.as_ref().unwrap_or(&"latest".to_string())
-      )
-    })
-    .collect::<Vec<String>>()
-    .join(" // ");
+  };
 
-  Some(images)
+  let report = Report::new(cfg, matches.is_present("dependencies"), auditor.as_ref());
+  print!("{}", report.render(format));
+  true
 }
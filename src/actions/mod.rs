@@ -1,13 +1,23 @@
+mod audit;
+mod cache;
+mod check;
+mod drift;
 mod fetch;
 mod list;
+mod outdated;
 mod register;
 mod register_all;
 mod show;
 mod unregister;
 mod versions;
 
+pub use audit::audit;
+pub use cache::cache;
+pub use check::check;
+pub use drift::drift;
 pub use fetch::fetch;
 pub use list::list;
+pub use outdated::outdated;
 pub use register::register;
 pub use register_all::register_all;
 pub use show::show;
@@ -0,0 +1,25 @@
+use crate::{
+  config::McaiWorkersConfig,
+  index::Index,
+  report::{Format, OutdatedReport},
+};
+use clap::{value_t, ArgMatches};
+
+/// Print the outdated dependencies of the workers, returns `false` when the index can not be opened
+pub fn outdated<'a>(cfg: &McaiWorkersConfig, matches: &ArgMatches<'a>) -> bool {
+  let format: Format = matches.value_of("format").unwrap().into();
+  let jobs = value_t!(matches, "jobs", usize).unwrap_or_else(|error| error.exit());
+  let index_url = matches.value_of("index").unwrap();
+
+  let index = match Index::new(index_url) {
+    Ok(index) => index,
+    Err(error) => {
+      eprintln!("Unable to open the index {}: {}", index_url, error);
+      return false;
+    }
+  };
+
+  let report = OutdatedReport::new(cfg, index_url, &index, jobs);
+  print!("{}", report.render(format));
+  true
+}
@@ -1,11 +1,57 @@
-use crate::config::{McaiWorkersConfig, Provider, RepoConfig};
+use crate::{
+  config::{McaiWorkersConfig, Provider, RepoConfig},
+  error::{Error, Result},
+  provider,
+};
 use clap::ArgMatches;
+use std::fs;
 
-pub fn register<'a>(cfg: &mut McaiWorkersConfig, matches: &ArgMatches<'a>) {
+/// Get the host of the provider from the command line arguments
+pub fn host_argument<'a>(provider: &Provider, matches: &ArgMatches<'a>) -> Option<String> {
+  match provider {
+    Provider::Github => matches.value_of("github-host").map(|host| host.to_string()),
+    Provider::Gitlab => matches.value_of("gitlab-host").map(|host| host.to_string()),
+    Provider::Gitea => matches.value_of("gitea-host").map(|host| host.to_string()),
+    Provider::Bitbucket => matches
+      .value_of("bitbucket-host")
+      .map(|host| host.to_string()),
+    Provider::Local | Provider::Git => None,
+  }
+}
+
+/// Get the repository from the command line arguments, local repositories being given by their absolute path
+pub fn repository_argument<'a>(provider: &Provider, matches: &ArgMatches<'a>) -> Result<String> {
   let repository = matches.value_of("repository").unwrap();
+  match provider {
+    Provider::Local => Ok(fs::canonicalize(repository)?.to_string_lossy().to_string()),
+    _ => Ok(repository.to_string()),
+  }
+}
+
+/// Register a repository, returning `false` when a local repository can not be found
+pub fn register<'a>(cfg: &mut McaiWorkersConfig, matches: &ArgMatches<'a>) -> bool {
   let provider: Provider = matches.value_of("provider").unwrap().into();
+  let repository = match repository_argument(&provider, matches) {
+    Ok(repository) => repository,
+    Err(error) => {
+      println!(
+        "Unable to find the local repository {}: {}",
+        matches.value_of("repository").unwrap(),
+        error
+      );
+      return false;
+    }
+  };
+  let repository = repository.as_str();
 
   let mut repo_config = RepoConfig::new(provider, repository);
+  repo_config.host = host_argument(&repo_config.provider, matches);
+  repo_config.api_url = matches
+    .value_of("api-url")
+    .map(|api_url| api_url.to_string());
+  repo_config.reference = matches
+    .value_of("ref")
+    .map(|reference| reference.to_string());
 
   if let Some(values) = matches.values_of("manifest") {
     for value in values {
@@ -19,6 +65,32 @@ pub fn register<'a>(cfg: &mut McaiWorkersConfig, matches: &ArgMatches<'a>) {
     }
   }
 
+  match provider::new(cfg, &repo_config, matches) {
+    Ok(provider) => {
+      let found = match &repo_config.reference {
+        Some(reference) => Ok(reference.clone()),
+        None => provider.default_branch(repository),
+      }
+      .and_then(|reference| {
+        provider
+          .commit_sha(repository, &reference)
+          .map(|commit| (reference, commit))
+      });
+
+      match found {
+        Ok((reference, commit)) => println!("Found {} ({} @ {})", repository, reference, commit),
+        Err(error) => println!(
+          "Warning: unable to find {} on {:?}: {}",
+          repository, repo_config.provider, error
+        ),
+      }
+    }
+    // the repository can be registered before its token is available
+    Err(Error::MissingToken(..)) => {}
+    Err(error) => println!("Warning: {}", error),
+  }
+
   cfg.add_repo(repo_config);
   cfg.store();
+  true
 }
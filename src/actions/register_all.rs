@@ -1,3 +1,4 @@
+use super::register::host_argument;
 use crate::config::{McaiWorkersConfig, Provider, RepoConfig};
 use clap::ArgMatches;
 use reqwest::blocking::Client;
@@ -8,9 +9,9 @@ pub fn register_all<'a>(cfg: &mut McaiWorkersConfig, matches: &ArgMatches<'a>) {
   let urls = matches.values_of("url").unwrap();
 
   let repositories: Vec<RepoConfig> = urls
-    .map(|url| fetch_content(&url))
+    .map(fetch_content)
     .filter(|content| content.is_some())
-    .map(|content| {
+    .flat_map(|content| {
       let description: Description = serde_json::from_str(&content.unwrap()).unwrap();
 
       if let Some(mcai_sdk_version) = description.mcai_sdk_version {
@@ -27,14 +28,23 @@ pub fn register_all<'a>(cfg: &mut McaiWorkersConfig, matches: &ArgMatches<'a>) {
         .map(|repo| {
           let mut rc = RepoConfig::new(repo.provider.clone(), &repo.name);
 
+          rc.host = repo
+            .host
+            .clone()
+            .or_else(|| host_argument(&repo.provider, matches));
+          rc.api_url = repo.api_url.clone();
+          rc.reference = repo.reference.clone();
+
           rc.manifest_filenames = repo.manifests.clone();
           rc.docker_filenames = repo.dockerfiles.clone();
+          rc.manifest_patterns = repo.manifest_patterns.clone();
+          rc.docker_patterns = repo.dockerfile_patterns.clone();
+          rc.python_patterns = repo.python_patterns.clone();
 
           rc
         })
         .collect::<Vec<RepoConfig>>()
     })
-    .flatten()
     .collect();
 
   for repository in repositories {
@@ -64,8 +74,19 @@ struct Description {
 struct Repository {
   provider: Provider,
   name: String,
+  host: Option<String>,
+  api_url: Option<String>,
+  #[serde(rename = "ref")]
+  reference: Option<String>,
   #[serde(default)]
   manifests: Vec<String>,
   #[serde(default)]
   dockerfiles: Vec<String>,
+  // glob patterns of the files to discover, the default patterns are used otherwise
+  #[serde(default)]
+  manifest_patterns: Vec<String>,
+  #[serde(default)]
+  dockerfile_patterns: Vec<String>,
+  #[serde(default)]
+  python_patterns: Vec<String>,
 }
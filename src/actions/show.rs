@@ -1,9 +1,39 @@
-use crate::config::McaiWorkersConfig;
+use crate::config::{short_commit, FileStatus, McaiWorkersConfig};
 use colored::Colorize;
 
 pub fn show(cfg: &McaiWorkersConfig) {
   for repo in &cfg.repos {
     let provider = format!("{:?}", repo.provider);
-    println!("{} {}", provider.green().bold(), repo.name);
+    let name = match &repo.host {
+      Some(host) => format!("{}/{}", host, repo.name),
+      None => repo.name.clone(),
+    };
+
+    match &repo.reference {
+      Some(reference) => println!("{} {} @ {}", provider.green().bold(), name, reference),
+      None => println!("{} {}", provider.green().bold(), name),
+    }
+
+    for file in &repo.fetched.files {
+      let commit = file
+        .commit
+        .as_deref()
+        .map(|commit| format!(" @ {}", short_commit(commit)))
+        .unwrap_or_default();
+      let status = match file.status {
+        FileStatus::Updated => "updated".green(),
+        FileStatus::Unchanged => "unchanged".normal(),
+        FileStatus::Migrated => "migrated".yellow(),
+      };
+      let fetched_at = file
+        .fetched_at
+        .map(|fetched_at| format!(", fetched {}", fetched_at.format("%Y-%m-%d %H:%M:%S UTC")))
+        .unwrap_or_default();
+
+      println!(
+        "  {:?} {}{} ({}{})",
+        file.kind, file.path, commit, status, fetched_at
+      );
+    }
   }
 }
@@ -1,16 +1,36 @@
-use crate::config::{McaiWorkersConfig, Provider};
+use super::register::{host_argument, repository_argument};
+use crate::{
+  cache,
+  config::{McaiWorkersConfig, Provider, RepoConfig},
+};
 use clap::ArgMatches;
 
 pub fn unregister<'a>(cfg: &mut McaiWorkersConfig, matches: &ArgMatches<'a>) {
-  let repository = matches.value_of("repository").unwrap();
   let provider: Provider = matches.value_of("provider").unwrap().into();
+  // local repositories which no longer exist are matched as given
+  let repository = repository_argument(&provider, matches)
+    .unwrap_or_else(|_| matches.value_of("repository").unwrap().to_string());
+  let host = host_argument(&provider, matches);
+  let reference = matches
+    .value_of("ref")
+    .map(|reference| reference.to_string());
 
-  let repos = cfg
-    .repos
-    .iter()
-    .filter(|repo| repo.name != repository && repo.provider == provider)
-    .cloned()
-    .collect();
+  let (removed, repos): (Vec<RepoConfig>, Vec<RepoConfig>) =
+    cfg.repos.drain(..).partition(|repo| {
+      repo.name == repository
+        && repo.provider == provider
+        && repo.host == host
+        && repo.reference == reference
+    });
+
+  for repo in removed {
+    if let Err(error) = cache::remove(&repo) {
+      println!(
+        "Warning: unable to remove the cache of {}: {}",
+        repo.name, error
+      );
+    }
+  }
 
   cfg.repos = repos;
 
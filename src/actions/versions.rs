@@ -4,7 +4,10 @@ use semver::Version;
 
 pub fn versions<'a>(cfg: &mut McaiWorkersConfig, matches: &ArgMatches<'a>) {
   if let Some(rust_version) = matches.value_of("rust") {
-    if let Ok(rust_version) = Version::parse(rust_version) {
+    // Rust versions are often given without patch, like `1.60`
+    let rust_version =
+      Version::parse(rust_version).or_else(|_| Version::parse(&format!("{}.0", rust_version)));
+    if let Ok(rust_version) = rust_version {
       cfg.rust_version = Some(rust_version);
     }
   }
@@ -0,0 +1,343 @@
+use crate::{
+  cargo,
+  config::{FileKind, RepoConfig},
+  error::{Error, Result},
+  index::Index,
+};
+use semver::{Version, VersionReq};
+use std::{
+  collections::{BTreeMap, HashMap},
+  fs,
+  path::Path,
+  sync::Mutex,
+};
+
+/// Advisory of the RustSec database, as the front matter of its Markdown file (or its legacy TOML file)
+#[derive(Debug, Deserialize)]
+struct AdvisoryFile {
+  advisory: AdvisoryMetadata,
+  #[serde(default)]
+  versions: AdvisoryVersions,
+}
+
+#[derive(Debug, Deserialize)]
+struct AdvisoryMetadata {
+  id: String,
+  package: String,
+  // given by the Markdown title in the current format
+  title: Option<String>,
+  url: Option<String>,
+  // kind of informational advisories, like `unmaintained`, `unsound` or `notice`
+  informational: Option<String>,
+  withdrawn: Option<toml::Value>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct AdvisoryVersions {
+  #[serde(default)]
+  patched: Vec<String>,
+  #[serde(default)]
+  unaffected: Vec<String>,
+}
+
+struct Advisory {
+  id: String,
+  title: String,
+  url: Option<String>,
+  // `vulnerability`, or the kind of informational advisories
+  kind: String,
+  patched: Vec<VersionReq>,
+  unaffected: Vec<VersionReq>,
+}
+
+impl Advisory {
+  /// Whether a version is neither patched nor unaffected
+  fn affects(&self, version: &Version) -> bool {
+    !self
+      .patched
+      .iter()
+      .chain(&self.unaffected)
+      .any(|requirement| requirement.matches(version))
+  }
+}
+
+/// Problem of a package locked by a worker
+#[derive(Debug, Serialize)]
+pub struct Finding {
+  // lockfile locking the package
+  pub path: String,
+  pub package: String,
+  pub version: String,
+  // `vulnerability`, `unmaintained`, `unsound`, `notice` or `yanked`
+  pub kind: String,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub advisory: Option<String>,
+  pub title: String,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub url: Option<String>,
+}
+
+impl Finding {
+  /// Describe the finding with its advisory, title and kind
+  pub fn description(&self) -> String {
+    match &self.advisory {
+      Some(advisory) => format!("{} {} ({})", advisory, self.title, self.kind),
+      None => format!("{} ({})", self.title, self.kind),
+    }
+  }
+}
+
+/// Checks of locked packages against a RustSec advisory database, and the yanked versions of an index
+pub struct Auditor {
+  advisories: HashMap<String, Vec<Advisory>>,
+  index: Option<Index>,
+  // yanked versions of the packages already looked up in the index
+  yanked: Mutex<BTreeMap<String, Vec<Version>>>,
+}
+
+impl Auditor {
+  /// Load the advisories of a local checkout of the RustSec advisory database (`https://github.com/rustsec/advisory-db`).
+  /// With an index, locked versions are checked against its yanked versions too.
+  pub fn new(database: &Path, index: Option<Index>) -> Result<Auditor> {
+    let mut advisories: HashMap<String, Vec<Advisory>> = HashMap::new();
+
+    let crates = fs::read_dir(database.join("crates"))
+      .map_err(|error| Error::AdvisoryDatabase(format!("{}: {}", database.display(), error)))?;
+    for directory in crates {
+      for file in fs::read_dir(directory?.path())? {
+        let path = file?.path();
+        let content = fs::read_to_string(&path)?;
+        let advisory = match path.extension().and_then(|extension| extension.to_str()) {
+          Some("md") => parse_markdown(&content),
+          Some("toml") => parse(&content, None),
+          _ => continue,
+        };
+
+        match advisory {
+          Ok(Some((package, advisory))) => advisories.entry(package).or_default().push(advisory),
+          Ok(None) => {}
+          Err(error) => eprintln!("Warning: ignored advisory {}: {}", path.display(), error),
+        }
+      }
+    }
+
+    for package_advisories in advisories.values_mut() {
+      package_advisories.sort_by(|advisory, other| advisory.id.cmp(&other.id));
+    }
+
+    if advisories.is_empty() {
+      return Err(Error::AdvisoryDatabase(format!(
+        "no advisory found in {}",
+        database.display()
+      )));
+    }
+
+    Ok(Auditor {
+      advisories,
+      index,
+      yanked: Mutex::new(BTreeMap::new()),
+    })
+  }
+
+  /// Check the packages of the fetched `Cargo.lock` files of a repository.
+  /// Lockfiles which can not be parsed, and packages which can not be looked up in the index, are given as errors.
+  pub fn audit(&self, repo: &RepoConfig) -> (Vec<Finding>, Vec<(String, String)>) {
+    let mut findings = vec![];
+    let mut errors = vec![];
+
+    for (path, content) in repo.fetched_files(FileKind::Lockfile) {
+      if !path.ends_with(cargo::LOCKFILE_FILENAME) {
+        continue;
+      }
+      let lockfile: cargo::Lockfile = match toml::from_str(content) {
+        Ok(lockfile) => lockfile,
+        Err(error) => {
+          errors.push((path, error.to_string()));
+          continue;
+        }
+      };
+
+      for (package, version) in lockfile.registry_packages() {
+        let advisories = self.advisories.get(package).into_iter().flatten();
+        for advisory in advisories.filter(|advisory| advisory.affects(&version)) {
+          findings.push(Finding {
+            path: path.clone(),
+            package: package.to_string(),
+            version: version.to_string(),
+            kind: advisory.kind.clone(),
+            advisory: Some(advisory.id.clone()),
+            title: advisory.title.clone(),
+            url: advisory.url.clone(),
+          });
+        }
+
+        match self.is_yanked(package, &version) {
+          Ok(true) => findings.push(Finding {
+            path: path.clone(),
+            package: package.to_string(),
+            version: version.to_string(),
+            kind: "yanked".to_string(),
+            advisory: None,
+            title: "Version yanked from the index".to_string(),
+            url: None,
+          }),
+          Ok(false) => {}
+          Err(error) => errors.push((
+            path.clone(),
+            format!("unable to check yanked versions of {}: {}", package, error),
+          )),
+        }
+      }
+    }
+
+    (findings, errors)
+  }
+
+  fn is_yanked(&self, package: &str, version: &Version) -> Result<bool> {
+    let index = match &self.index {
+      Some(index) => index,
+      None => return Ok(false),
+    };
+
+    let mut yanked = self
+      .yanked
+      .lock()
+      .unwrap_or_else(|error| error.into_inner());
+    if !yanked.contains_key(package) {
+      yanked.insert(package.to_string(), index.yanked_versions(package)?);
+    }
+    Ok(yanked[package].contains(version))
+  }
+}
+
+/// Parse an advisory in the Markdown format, with its metadata in a TOML code block followed by its title
+fn parse_markdown(content: &str) -> std::result::Result<Option<(String, Advisory)>, String> {
+  let front_matter = content
+    .strip_prefix("```toml")
+    .and_then(|content| content.split_once("\n```"))
+    .ok_or("missing TOML front matter")?;
+
+  let title = front_matter
+    .1
+    .lines()
+    .find_map(|line| line.strip_prefix("# "))
+    .map(|title| title.trim().to_string());
+
+  parse(front_matter.0, title)
+}
+
+/// Parse the metadata of an advisory, withdrawn advisories being ignored
+fn parse(
+  content: &str,
+  title: Option<String>,
+) -> std::result::Result<Option<(String, Advisory)>, String> {
+  let file: AdvisoryFile = toml::from_str(content).map_err(|error| error.to_string())?;
+  if file.advisory.withdrawn.is_some() {
+    return Ok(None);
+  }
+
+  let requirements = |requirements: &[String]| {
+    requirements
+      .iter()
+      .map(|requirement| VersionReq::parse(requirement).map_err(|error| error.to_string()))
+      .collect::<std::result::Result<Vec<_>, _>>()
+  };
+
+  let id = file.advisory.id;
+  let advisory = Advisory {
+    title: title.or(file.advisory.title).unwrap_or_else(|| id.clone()),
+    id,
+    url: file.advisory.url,
+    kind: file
+      .advisory
+      .informational
+      .unwrap_or_else(|| "vulnerability".to_string()),
+    patched: requirements(&file.versions.patched)?,
+    unaffected: requirements(&file.versions.unaffected)?,
+  };
+  Ok(Some((file.advisory.package, advisory)))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn markdown(metadata: &str, versions: &str) -> String {
+    format!(
+      "```toml\n[advisory]\nid = \"RUSTSEC-2021-0001\"\npackage = \"lapin\"\ndate = 2021-01-01\n{}\n\n[versions]\n{}\n```\n\n# Heap overflow in frame parsing\n\nDetails.\n",
+      metadata, versions
+    )
+  }
+
+  #[test]
+  fn parse_advisories() {
+    let content = markdown(
+      "url = \"https://example.com/lapin\"",
+      "patched = [\">= 1.6.2\"]",
+    );
+    let (package, advisory) = parse_markdown(&content).unwrap().unwrap();
+    assert_eq!(package, "lapin");
+    assert_eq!(advisory.id, "RUSTSEC-2021-0001");
+    assert_eq!(advisory.title, "Heap overflow in frame parsing");
+    assert_eq!(advisory.url.as_deref(), Some("https://example.com/lapin"));
+    assert_eq!(advisory.kind, "vulnerability");
+
+    let content = markdown("informational = \"unmaintained\"", "");
+    let (_, advisory) = parse_markdown(&content).unwrap().unwrap();
+    assert_eq!(advisory.kind, "unmaintained");
+    assert!(advisory.patched.is_empty() && advisory.unaffected.is_empty());
+
+    let content = markdown("withdrawn = 2021-02-01", "patched = [\">= 1.6.2\"]");
+    assert!(parse_markdown(&content).unwrap().is_none());
+
+    let content = markdown("", "patched = [\"not a version\"]");
+    assert!(parse_markdown(&content).is_err());
+
+    assert!(parse_markdown("# Heap overflow in frame parsing\n").is_err());
+  }
+
+  #[test]
+  fn parse_legacy_advisories() {
+    let content = "[advisory]\nid = \"RUSTSEC-2019-0001\"\npackage = \"tokio\"\ntitle = \"Data race\"\n\n[versions]\npatched = [\">= 0.1.22\"]\n";
+    let (package, advisory) = parse(content, None).unwrap().unwrap();
+    assert_eq!(package, "tokio");
+    assert_eq!(advisory.title, "Data race");
+
+    let content = "[advisory]\nid = \"RUSTSEC-2019-0001\"\npackage = \"tokio\"\n";
+    let (_, advisory) = parse(content, None).unwrap().unwrap();
+    assert_eq!(advisory.title, "RUSTSEC-2019-0001");
+  }
+
+  #[test]
+  fn affected_versions() {
+    let content = markdown(
+      "",
+      "patched = [\">= 1.6.2, < 2.0.0\", \">= 2.1.1\"]\nunaffected = [\"< 1.0.0\"]",
+    );
+    let (_, advisory) = parse_markdown(&content).unwrap().unwrap();
+
+    let cases = vec![
+      ("0.9.9", false),
+      ("1.0.0", true),
+      ("1.6.1", true),
+      ("1.6.2", false),
+      ("1.9.0", false),
+      ("2.0.0", true),
+      ("2.1.0", true),
+      ("2.1.1", false),
+      ("3.0.0", false),
+    ];
+    for (version, affected) in cases {
+      assert_eq!(
+        advisory.affects(&Version::parse(version).unwrap()),
+        affected,
+        "{}",
+        version
+      );
+    }
+
+    let content = markdown("informational = \"unmaintained\"", "");
+    let (_, advisory) = parse_markdown(&content).unwrap().unwrap();
+    assert!(advisory.affects(&Version::parse("1.0.0").unwrap()));
+  }
+}
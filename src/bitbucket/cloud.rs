@@ -0,0 +1,131 @@
+use super::http_client;
+use crate::{
+  error::{Error, Result},
+  http,
+  provider::{self, FileResponse, SourceProvider, TreeFile},
+};
+use reqwest::blocking::Client;
+use serde::de::DeserializeOwned;
+
+static TREE_MAX_DEPTH: usize = 32;
+
+/// Client of Bitbucket Cloud, where repositories are named `workspace/repository`
+pub struct BitbucketCloud {
+  api_url: String,
+  client: Client,
+}
+
+impl BitbucketCloud {
+  pub fn new(api_url: &str, credentials: &str) -> Result<BitbucketCloud> {
+    Ok(BitbucketCloud {
+      api_url: api_url.trim_end_matches('/').to_string(),
+      client: http_client(credentials)?,
+    })
+  }
+
+  fn get<T: DeserializeOwned>(&self, url: &str) -> Result<T> {
+    provider::json(http::send(self.client.get(url))?)
+  }
+
+  fn repository_url(&self, repository: &str) -> Result<String> {
+    let (workspace, repo_name) = provider::owner_and_name(repository)?;
+    Ok(format!(
+      "{}/repositories/{}/{}",
+      self.api_url, workspace, repo_name
+    ))
+  }
+}
+
+impl SourceProvider for BitbucketCloud {
+  fn get_file(
+    &self,
+    repository: &str,
+    reference: &str,
+    filename: &str,
+    etag: Option<&str>,
+  ) -> Result<Option<FileResponse>> {
+    let url = format!(
+      "{}/src/{}/{}",
+      self.repository_url(repository)?,
+      provider::encode(reference),
+      provider::encode_path(filename)
+    );
+
+    provider::request_file(self.client.get(&url), etag, |response| {
+      Ok((response.text()?, None))
+    })
+  }
+
+  fn list_tree(&self, repository: &str, reference: &str) -> Result<Vec<TreeFile>> {
+    let mut paths = vec![];
+    let mut next = Some(format!(
+      "{}/src/{}/?max_depth={}&pagelen=100",
+      self.repository_url(repository)?,
+      provider::encode(reference),
+      TREE_MAX_DEPTH
+    ));
+
+    while let Some(url) = next {
+      let page: Page<TreeEntry> = self.get(&url)?;
+
+      paths.extend(
+        page
+          .values
+          .into_iter()
+          .filter(|entry| entry._type == "commit_file")
+          .map(|entry| TreeFile {
+            path: entry.path,
+            sha: None,
+          }),
+      );
+      next = page.next;
+    }
+
+    Ok(paths)
+  }
+
+  fn default_branch(&self, repository: &str) -> Result<String> {
+    let response: Repository = self.get(&self.repository_url(repository)?)?;
+    response
+      .mainbranch
+      .map(|branch| branch.name)
+      .ok_or_else(|| Error::Api(format!("{} has no main branch", repository)))
+  }
+
+  fn commit_sha(&self, repository: &str, reference: &str) -> Result<String> {
+    let response: Commit = self.get(&format!(
+      "{}/commit/{}",
+      self.repository_url(repository)?,
+      provider::encode(reference)
+    ))?;
+    Ok(response.hash)
+  }
+}
+
+#[derive(Debug, Deserialize)]
+struct Page<T> {
+  values: Vec<T>,
+  next: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TreeEntry {
+  path: String,
+  #[serde(rename = "type")]
+  _type: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Repository {
+  mainbranch: Option<Branch>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Branch {
+  name: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Commit {
+  hash: String,
+}
@@ -0,0 +1,34 @@
+mod cloud;
+mod server;
+
+pub use cloud::BitbucketCloud;
+pub use server::BitbucketServer;
+
+use crate::{error::Result, PROJECT_NAME};
+use reqwest::{
+  blocking::Client,
+  header::{HeaderMap, HeaderValue, AUTHORIZATION, USER_AGENT},
+};
+
+pub static CLOUD_HOST: &str = "bitbucket.org";
+
+/// Get the API base URL of a Bitbucket host, Server and Data Center instances serve it under `/rest/api/1.0`
+pub fn api_url(host: &str) -> String {
+  if host == CLOUD_HOST {
+    "https://api.bitbucket.org/2.0".to_string()
+  } else {
+    format!("https://{}/rest/api/1.0", host)
+  }
+}
+
+/// Build an HTTP client authenticated with `username:app_password` credentials
+fn http_client(credentials: &str) -> Result<Client> {
+  let mut headers = HeaderMap::new();
+  headers.insert(
+    AUTHORIZATION,
+    HeaderValue::from_str(&format!("Basic {}", base64::encode(credentials)))?,
+  );
+  headers.insert(USER_AGENT, HeaderValue::from_static(PROJECT_NAME));
+
+  Ok(Client::builder().default_headers(headers).build()?)
+}
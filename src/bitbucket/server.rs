@@ -0,0 +1,123 @@
+use super::http_client;
+use crate::{
+  error::Result,
+  http,
+  provider::{self, FileResponse, SourceProvider, TreeFile},
+};
+use reqwest::blocking::Client;
+use serde::de::DeserializeOwned;
+
+static TREE_PAGE_SIZE: usize = 1000;
+
+/// Client of Bitbucket Server and Data Center, where repositories are named `PROJECT/repository`
+pub struct BitbucketServer {
+  api_url: String,
+  client: Client,
+}
+
+impl BitbucketServer {
+  pub fn new(api_url: &str, credentials: &str) -> Result<BitbucketServer> {
+    Ok(BitbucketServer {
+      api_url: api_url.trim_end_matches('/').to_string(),
+      client: http_client(credentials)?,
+    })
+  }
+
+  fn get<T: DeserializeOwned>(&self, url: &str) -> Result<T> {
+    provider::json(http::send(self.client.get(url))?)
+  }
+
+  fn repository_url(&self, repository: &str) -> Result<String> {
+    let (project, repo_name) = provider::owner_and_name(repository)?;
+    Ok(format!(
+      "{}/projects/{}/repos/{}",
+      self.api_url, project, repo_name
+    ))
+  }
+}
+
+impl SourceProvider for BitbucketServer {
+  fn get_file(
+    &self,
+    repository: &str,
+    reference: &str,
+    filename: &str,
+    etag: Option<&str>,
+  ) -> Result<Option<FileResponse>> {
+    let url = format!(
+      "{}/raw/{}?at={}",
+      self.repository_url(repository)?,
+      provider::encode_path(filename),
+      provider::encode(reference)
+    );
+
+    provider::request_file(self.client.get(&url), etag, |response| {
+      Ok((response.text()?, None))
+    })
+  }
+
+  fn list_tree(&self, repository: &str, reference: &str) -> Result<Vec<TreeFile>> {
+    let mut paths = vec![];
+    let mut start = Some(0);
+
+    while let Some(page_start) = start {
+      let page: Page<String> = self.get(&format!(
+        "{}/files?at={}&limit={}&start={}",
+        self.repository_url(repository)?,
+        provider::encode(reference),
+        TREE_PAGE_SIZE,
+        page_start
+      ))?;
+
+      paths.extend(
+        page
+          .values
+          .into_iter()
+          .map(|path| TreeFile { path, sha: None }),
+      );
+      start = if page.is_last_page {
+        None
+      } else {
+        page.next_page_start
+      };
+    }
+
+    Ok(paths)
+  }
+
+  fn default_branch(&self, repository: &str) -> Result<String> {
+    let response: Branch = self.get(&format!(
+      "{}/branches/default",
+      self.repository_url(repository)?
+    ))?;
+    Ok(response.display_id)
+  }
+
+  fn commit_sha(&self, repository: &str, reference: &str) -> Result<String> {
+    let response: Commit = self.get(&format!(
+      "{}/commits/{}",
+      self.repository_url(repository)?,
+      provider::encode(reference)
+    ))?;
+    Ok(response.id)
+  }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Page<T> {
+  values: Vec<T>,
+  is_last_page: bool,
+  next_page_start: Option<usize>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Branch {
+  display_id: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Commit {
+  id: String,
+}
@@ -0,0 +1,125 @@
+use crate::{
+  config::{FetchedData, RepoConfig},
+  error::{Error, Result},
+  PROJECT_NAME,
+};
+use directories::ProjectDirs;
+use percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use std::{
+  fs, io,
+  path::{Path, PathBuf},
+};
+
+static REPOS_DIRECTORY: &str = "repos";
+static GIT_DIRECTORY: &str = "git";
+static FETCHED_FILENAME: &str = "fetched.toml";
+// characters kept in the names of the repository directories, others are percent-encoded
+const NAME_COMPONENT: &AsciiSet = &NON_ALPHANUMERIC.remove(b'-').remove(b'.').remove(b'_');
+
+/// Get the directory where data fetched from repositories is cached
+pub fn directory() -> PathBuf {
+  let project = ProjectDirs::from("rs", "", PROJECT_NAME).unwrap();
+  project.cache_dir().to_path_buf()
+}
+
+/// Get the directory where repositories reachable by a git URL are cloned
+pub fn git_directory() -> PathBuf {
+  directory().join(GIT_DIRECTORY)
+}
+
+/// Get the directory of the repository caches
+pub fn repos_directory() -> PathBuf {
+  directory().join(REPOS_DIRECTORY)
+}
+
+/// Get the cache directory of a repository, named after its provider, host, name and reference
+pub fn repo_directory(repo: &RepoConfig) -> PathBuf {
+  let encode = |component: &str| utf8_percent_encode(component, NAME_COMPONENT).to_string();
+
+  let mut name = repo.provider.as_str().to_string();
+  if let Some(host) = &repo.host {
+    name = format!("{}+{}", name, encode(host));
+  }
+  name = format!("{}+{}", name, encode(&repo.name));
+  if let Some(reference) = &repo.reference {
+    name = format!("{}@{}", name, encode(reference));
+  }
+
+  repos_directory().join(name)
+}
+
+/// Load the data fetched from a repository, if it has been cached
+pub fn load(repo: &RepoConfig) -> Result<Option<FetchedData>> {
+  let content = match fs::read_to_string(repo_directory(repo).join(FETCHED_FILENAME)) {
+    Ok(content) => content,
+    Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(None),
+    Err(error) => return Err(error.into()),
+  };
+
+  toml::from_str(&content)
+    .map(Some)
+    .map_err(|error| Error::Cache(error.to_string()))
+}
+
+/// Store the data fetched from a repository, when it has been fetched and differs from the cached data.
+/// It is written to a temporary file first, so an interrupted write keeps the previous data.
+pub fn store(repo: &RepoConfig) -> Result<()> {
+  let content = toml::to_string(&repo.fetched).map_err(|error| Error::Cache(error.to_string()))?;
+
+  let directory = repo_directory(repo);
+  let path = directory.join(FETCHED_FILENAME);
+  match fs::read_to_string(&path) {
+    Ok(cached) if cached == content => return Ok(()),
+    Ok(_) => {}
+    Err(error) if error.kind() == io::ErrorKind::NotFound => {
+      if repo.fetched.is_empty() {
+        return Ok(());
+      }
+    }
+    Err(error) => return Err(error.into()),
+  }
+
+  fs::create_dir_all(&directory)?;
+  let temporary_path = path.with_extension("toml.tmp");
+  fs::write(&temporary_path, content)?;
+  fs::rename(&temporary_path, &path)?;
+  Ok(())
+}
+
+/// Remove the cache of a repository
+pub fn remove(repo: &RepoConfig) -> Result<()> {
+  remove_directory(&repo_directory(repo))
+}
+
+/// Remove the whole cache directory, including git clones
+pub fn clear() -> Result<()> {
+  remove_directory(&directory())
+}
+
+/// Get the size in bytes of the files of a directory and its subdirectories
+pub fn size(path: &Path) -> u64 {
+  let metadata = match fs::symlink_metadata(path) {
+    Ok(metadata) => metadata,
+    Err(_) => return 0,
+  };
+
+  if !metadata.is_dir() {
+    return metadata.len();
+  }
+
+  fs::read_dir(path)
+    .map(|entries| {
+      entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| size(&entry.path()))
+        .sum()
+    })
+    .unwrap_or_default()
+}
+
+fn remove_directory(directory: &Path) -> Result<()> {
+  match fs::remove_dir_all(directory) {
+    Err(error) if error.kind() != io::ErrorKind::NotFound => Err(error.into()),
+    _ => Ok(()),
+  }
+}
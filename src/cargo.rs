@@ -0,0 +1,286 @@
+use crate::{
+  config::{FetchedFile, FileKind, RepoConfig},
+  error::{Error, Result},
+  provider::TreeFile,
+};
+use cargo_toml::{AbstractFilesystem, Error as ManifestError, Manifest, Value};
+use globset::GlobBuilder;
+use semver::{Version, VersionReq};
+use std::{collections::HashSet, io, path::PathBuf};
+
+static MANIFEST_FILENAME: &str = "Cargo.toml";
+pub static LOCKFILE_FILENAME: &str = "Cargo.lock";
+
+/// Packages locked by a `Cargo.lock`, or a `poetry.lock` which shares the same layout
+#[derive(Debug, Deserialize)]
+pub struct Lockfile {
+  #[serde(default)]
+  package: Vec<LockedPackage>,
+}
+
+#[derive(Debug, Deserialize)]
+struct LockedPackage {
+  name: String,
+  version: String,
+  // registry or git repository of the package, missing for the packages of the workspace
+  source: Option<String>,
+}
+
+impl Lockfile {
+  /// Get the packages locked from a registry, with their version
+  pub fn registry_packages(&self) -> impl Iterator<Item = (&str, Version)> {
+    self
+      .package
+      .iter()
+      .filter(|package| {
+        package
+          .source
+          .as_deref()
+          .is_some_and(|source| source.starts_with("registry+") || source.starts_with("sparse+"))
+      })
+      .filter_map(|package| {
+        Version::parse(&package.version)
+          .ok()
+          .map(|version| (package.name.as_str(), version))
+      })
+  }
+
+  /// Get the locked version of a package, with whether it satisfies the requirement.
+  /// Among several locked versions, the one satisfying the requirement is preferred.
+  pub fn locked_version<F>(
+    &self,
+    is_package: F,
+    requirement: Option<&VersionReq>,
+  ) -> Option<(Version, bool)>
+  where
+    F: Fn(&str) -> bool,
+  {
+    let versions: Vec<Version> = self
+      .package
+      .iter()
+      .filter(|package| is_package(&package.name))
+      .filter_map(|package| Version::parse(&package.version).ok())
+      .collect();
+
+    // requirements which are not versions (like paths) are satisfied by any version
+    let satisfies =
+      |version: &Version| requirement.is_none_or(|requirement| requirement.matches(version));
+
+    versions
+      .iter()
+      .find(|version| satisfies(version))
+      .or_else(|| versions.first())
+      .map(|version| (version.clone(), satisfies(version)))
+  }
+}
+
+/// Parse a version requirement of a manifest.
+/// Versions without operator are caret requirements, as for Cargo (`2.0` being `^2.0`).
+pub fn requirement(requirement: &str) -> Option<VersionReq> {
+  let comparators: Vec<String> = requirement
+    .split(',')
+    .map(|comparator| comparator.trim())
+    .map(|comparator| {
+      if comparator.starts_with(|c: char| c.is_ascii_digit()) {
+        format!("^{}", comparator)
+      } else {
+        comparator.to_string()
+      }
+    })
+    .collect();
+
+  VersionReq::parse(&comparators.join(", ")).ok()
+}
+
+/// Get the directory of a path of a repository, empty at the root of the repository
+pub fn parent_directory(path: &str) -> &str {
+  path
+    .rsplit_once('/')
+    .map(|(directory, _)| directory)
+    .unwrap_or_default()
+}
+
+/// Join a relative path to a directory of a repository, resolving `.` and `..` components
+pub fn join(directory: &str, path: &str) -> String {
+  let mut components: Vec<&str> = directory
+    .split('/')
+    .filter(|component| !component.is_empty())
+    .collect();
+
+  for component in path.split('/') {
+    match component {
+      "" | "." => {}
+      ".." => {
+        components.pop();
+      }
+      component => components.push(component),
+    }
+  }
+
+  components.join("/")
+}
+
+/// Find the manifests of the members of the workspaces declared by the given manifests, with their blob SHA.
+/// Members declared with glob patterns can only be found in the repository tree.
+pub fn workspace_members(
+  manifests: &[&FetchedFile],
+  tree: Option<&[TreeFile]>,
+) -> Vec<(String, Option<String>)> {
+  let mut members = vec![];
+
+  for manifest in manifests {
+    let workspace = match Manifest::from_str(&manifest.content) {
+      Ok(Manifest {
+        workspace: Some(workspace),
+        ..
+      }) => workspace,
+      _ => continue,
+    };
+
+    let directory = parent_directory(&manifest.path);
+    let excluded: Vec<String> = workspace
+      .exclude
+      .iter()
+      .map(|exclude| join(directory, exclude))
+      .collect();
+
+    for member in &workspace.members {
+      let path = join(&join(directory, member), MANIFEST_FILENAME);
+
+      match tree {
+        Some(tree) => {
+          let pattern = match GlobBuilder::new(&path).literal_separator(true).build() {
+            Ok(glob) => glob.compile_matcher(),
+            Err(_) => continue,
+          };
+
+          members.extend(
+            tree
+              .iter()
+              .filter(|file| pattern.is_match(&file.path))
+              .filter(|file| {
+                !excluded
+                  .iter()
+                  .any(|exclude| exclude == parent_directory(&file.path))
+              })
+              .map(|file| (file.path.clone(), file.sha.clone())),
+          );
+        }
+        None if !member.contains(&['*', '?', '['][..]) => members.push((path, None)),
+        None => {}
+      }
+    }
+  }
+
+  members
+}
+
+/// Parse the fetched manifests of a repository, completed with the properties inherited from their workspace
+pub fn manifests(repo: &RepoConfig) -> Vec<(String, Result<Manifest>)> {
+  let files = repo.fetched_files(FileKind::Manifest);
+
+  files
+    .iter()
+    .map(|(path, content)| (path.clone(), parse(path, content, &files)))
+    .collect()
+}
+
+/// Count the members of the workspace declared by a fetched manifest, among the fetched manifests.
+/// Members declared with glob patterns are counted by the fetched manifests they match.
+pub fn workspace_member_count(repo: &RepoConfig, manifest_path: &str) -> usize {
+  let manifests: Vec<TreeFile> = repo
+    .files(FileKind::Manifest)
+    .map(|file| TreeFile {
+      path: file.path.clone(),
+      sha: None,
+    })
+    .collect();
+
+  let mut members: Vec<String> = repo
+    .files(FileKind::Manifest)
+    .filter(|file| file.path == manifest_path)
+    .flat_map(|manifest| workspace_members(&[manifest], Some(&manifests)))
+    .map(|(path, _)| path)
+    .collect();
+  members.sort();
+  members.dedup();
+  members.len()
+}
+
+/// Parse the lockfile of a manifest, next to it or in a parent directory for workspace members
+pub fn lockfile(repo: &RepoConfig, manifest_path: &str) -> Option<Result<Lockfile>> {
+  let lockfiles = repo.fetched_files(FileKind::Lockfile);
+
+  let mut directory = parent_directory(manifest_path);
+  loop {
+    let path = join(directory, LOCKFILE_FILENAME);
+    if let Some((_, content)) = lockfiles.iter().find(|(other, _)| *other == path) {
+      return Some(toml::from_str(content).map_err(|error| Error::Manifest(error.to_string())));
+    }
+
+    if directory.is_empty() {
+      return None;
+    }
+    directory = parent_directory(directory);
+  }
+}
+
+fn parse(path: &str, content: &str, manifests: &[(String, &str)]) -> Result<Manifest> {
+  let mut manifest = Manifest::from_str(content)?;
+
+  let repository = FetchedManifests {
+    directory: parent_directory(path),
+    manifests,
+  };
+  manifest.complete_from_abstract_filesystem::<Value, _>(repository, None)?;
+
+  Ok(manifest)
+}
+
+/// View of the fetched manifests of a repository, from the directory of one of them
+struct FetchedManifests<'a> {
+  directory: &'a str,
+  manifests: &'a [(String, &'a str)],
+}
+
+impl AbstractFilesystem for FetchedManifests<'_> {
+  fn file_names_in(&self, _rel_path: &str) -> io::Result<HashSet<Box<str>>> {
+    // only manifests are fetched, the source files of packages are unknown
+    Ok(HashSet::new())
+  }
+
+  fn parse_root_workspace(
+    &self,
+    rel_path_hint: Option<&str>,
+  ) -> std::result::Result<(Manifest<Value>, PathBuf), ManifestError> {
+    // the workspace is given by `package.workspace`, or is the closest parent directory declaring one
+    let directories = match rel_path_hint {
+      Some(hint) => vec![join(self.directory, hint)],
+      None => {
+        let mut directories = vec![];
+        let mut directory = self.directory;
+        while !directory.is_empty() {
+          directory = parent_directory(directory);
+          directories.push(directory.to_string());
+        }
+        directories
+      }
+    };
+
+    for directory in directories {
+      let path = join(&directory, MANIFEST_FILENAME);
+
+      if let Some((_, content)) = self.manifests.iter().find(|(other, _)| *other == path) {
+        let manifest = Manifest::from_str(content)?;
+        if manifest.workspace.is_some() {
+          return Ok((manifest, PathBuf::from("/").join(directory)));
+        }
+      }
+    }
+
+    Err(ManifestError::WorkspaceIntegrity(format!(
+      "no fetched workspace manifest for {}",
+      join(self.directory, MANIFEST_FILENAME)
+    )))
+  }
+}
@@ -1,7 +1,33 @@
-use crate::PROJECT_NAME;
+use crate::{cache, PROJECT_NAME};
+use chrono::{DateTime, Utc};
 use directories::ProjectDirs;
 use semver::Version;
 
+// patterns of the files discovered in repository trees, when a repository does not declare any
+static DEFAULT_MANIFEST_PATTERNS: &[&str] = &["**/Cargo.toml"];
+static DEFAULT_DOCKER_PATTERNS: &[&str] = &[
+  "**/Dockerfile*",
+  "**/*.Dockerfile",
+  "**/*.dockerfile",
+  "**/Containerfile*",
+];
+static DEFAULT_PYTHON_PATTERNS: &[&str] = &[
+  "**/requirements*.txt",
+  "**/pyproject.toml",
+  "**/setup.py",
+  "**/poetry.lock",
+];
+static DEFAULT_TOOLCHAIN_PATTERNS: &[&str] = &["**/rust-toolchain", "**/rust-toolchain.toml"];
+// Python files fetched at the root of repositories which tree is not available
+static DEFAULT_PYTHON_PATHS: &[&str] = &[
+  "requirements.txt",
+  "pyproject.toml",
+  "setup.py",
+  "poetry.lock",
+];
+// Rust toolchain files fetched at the root of repositories which tree is not available
+static DEFAULT_TOOLCHAIN_PATHS: &[&str] = &["rust-toolchain", "rust-toolchain.toml"];
+
 #[derive(Debug, Default, Deserialize, Serialize)]
 pub struct McaiWorkersConfig {
   pub rust_version: Option<Version>,
@@ -11,7 +37,21 @@ pub struct McaiWorkersConfig {
 
 impl McaiWorkersConfig {
   pub fn open() -> Self {
-    confy::load(PROJECT_NAME).unwrap()
+    let mut cfg: Self = confy::load(PROJECT_NAME).unwrap();
+    for repo in &mut cfg.repos {
+      repo.migrate();
+
+      // fetched data stored in the configuration by previous versions is kept until cached
+      match cache::load(repo) {
+        Ok(Some(fetched)) => repo.fetched = fetched,
+        Ok(None) => {}
+        Err(error) => eprintln!(
+          "Warning: unable to read the cache of {}: {}",
+          repo.name, error
+        ),
+      }
+    }
+    cfg
   }
 
   pub fn add_repo(&mut self, repo: RepoConfig) {
@@ -25,6 +65,15 @@ impl McaiWorkersConfig {
   pub fn store(&self) {
     confy::store(PROJECT_NAME, self).unwrap();
 
+    for repo in &self.repos {
+      if let Err(error) = cache::store(repo) {
+        eprintln!(
+          "Warning: unable to cache the files of {}: {}",
+          repo.name, error
+        );
+      }
+    }
+
     let project = ProjectDirs::from("rs", "", "mcai-workers").unwrap();
     println!(
       "Stored configuration in folder: {}",
@@ -37,14 +86,64 @@ impl McaiWorkersConfig {
 pub struct RepoConfig {
   pub name: String,
   pub provider: Provider,
-  // list of Cargo.toml contents for this repository
-  pub manifest_contents: Vec<String>,
+  // host of self-hosted instances, the public instance of the provider is used otherwise
+  #[serde(default)]
+  pub host: Option<String>,
+  // base URL of the provider API, deduced from the host otherwise
+  #[serde(default)]
+  pub api_url: Option<String>,
+  // branch, tag or commit to fetch, the default branch is used otherwise
+  #[serde(default, rename = "ref")]
+  pub reference: Option<String>,
+  // list of Cargo.toml contents stored by previous versions, migrated to files when opened
+  #[serde(default, skip_serializing)]
+  manifest_contents: Vec<String>,
   // list of Cargo.toml filenames for this repository
   pub manifest_filenames: Vec<String>,
-  // list of Dockerfile contents for this repository
-  pub docker_contents: Vec<String>,
+  // list of Dockerfile contents stored by previous versions, migrated to files when opened
+  #[serde(default, skip_serializing)]
+  docker_contents: Vec<String>,
   // list of Dockerfile filenames for this repository
   pub docker_filenames: Vec<String>,
+  // glob patterns of the Cargo.toml files to discover in the repository tree
+  #[serde(default)]
+  pub manifest_patterns: Vec<String>,
+  // glob patterns of the Dockerfiles to discover in the repository tree
+  #[serde(default)]
+  pub docker_patterns: Vec<String>,
+  // glob patterns of the Python requirement files to discover in the repository tree
+  #[serde(default)]
+  pub python_patterns: Vec<String>,
+  // data fetched from this repository, stored in the cache directory.
+  // It is read from the configuration only to migrate files written by previous versions.
+  #[serde(default, flatten, skip_serializing)]
+  pub fetched: FetchedData,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct FetchedData {
+  // reference of the last fetched contents
+  #[serde(default)]
+  pub fetched_reference: Option<String>,
+  // commit SHA of the last fetched contents
+  #[serde(default)]
+  pub commit: Option<String>,
+  // error of the last fetch, the previously fetched files are kept
+  #[serde(default)]
+  pub last_error: Option<String>,
+  // files fetched from this repository
+  #[serde(default)]
+  pub files: Vec<FetchedFile>,
+}
+
+impl FetchedData {
+  /// Check whether the repository has never been fetched, successfully or not
+  pub fn is_empty(&self) -> bool {
+    self.fetched_reference.is_none()
+      && self.commit.is_none()
+      && self.last_error.is_none()
+      && self.files.is_empty()
+  }
 }
 
 impl RepoConfig {
@@ -52,32 +151,232 @@ impl RepoConfig {
     RepoConfig {
       name: name.to_string(),
       provider,
+      host: None,
+      api_url: None,
+      reference: None,
       manifest_contents: vec![],
       manifest_filenames: vec![],
       docker_contents: vec![],
       docker_filenames: vec![],
+      manifest_patterns: vec![],
+      docker_patterns: vec![],
+      python_patterns: vec![],
+      fetched: FetchedData::default(),
     }
   }
+
+  /// Convert the contents stored by previous versions into file records, matched with the configured paths.
+  /// Contents which do not match the configured paths one to one are dropped, to be fetched again.
+  fn migrate(&mut self) {
+    let matched = |kind: FileKind, paths: Vec<String>, contents: Vec<String>| {
+      if paths.len() != contents.len() {
+        return vec![];
+      }
+      paths
+        .into_iter()
+        .zip(contents)
+        .map(|(path, content)| (kind, path, content))
+        .collect()
+    };
+    let manifests = matched(
+      FileKind::Manifest,
+      self.manifest_paths(),
+      std::mem::take(&mut self.manifest_contents),
+    );
+    let dockerfiles = matched(
+      FileKind::Dockerfile,
+      self.docker_paths(),
+      std::mem::take(&mut self.docker_contents),
+    );
+    let commit = &self.fetched.commit;
+
+    let migrated: Vec<FetchedFile> = manifests
+      .into_iter()
+      .chain(dockerfiles)
+      .map(|(kind, path, content)| FetchedFile {
+        path,
+        kind,
+        status: FileStatus::Migrated,
+        commit: commit.clone(),
+        fetched_at: None,
+        sha: None,
+        etag: None,
+        content,
+      })
+      .collect();
+    self.fetched.files.extend(migrated);
+  }
+
+  /// Get the fetched files of a kind
+  pub fn files(&self, kind: FileKind) -> impl Iterator<Item = &FetchedFile> {
+    self
+      .fetched
+      .files
+      .iter()
+      .filter(move |file| file.kind == kind)
+  }
+
+  /// Get the paths and fetched contents of a kind of file
+  pub fn fetched_files(&self, kind: FileKind) -> Vec<(String, &str)> {
+    self
+      .files(kind)
+      .map(|file| (file.path.clone(), file.content.as_str()))
+      .collect()
+  }
+
+  pub fn manifest_paths(&self) -> Vec<String> {
+    if self.manifest_filenames.is_empty() {
+      vec!["Cargo.toml".to_string()]
+    } else {
+      self.manifest_filenames.clone()
+    }
+  }
+
+  pub fn docker_paths(&self) -> Vec<String> {
+    if self.docker_filenames.is_empty() {
+      vec!["Dockerfile".to_string()]
+    } else {
+      self.docker_filenames.clone()
+    }
+  }
+
+  pub fn python_paths(&self) -> Vec<String> {
+    to_strings(DEFAULT_PYTHON_PATHS)
+  }
+
+  pub fn toolchain_paths(&self) -> Vec<String> {
+    to_strings(DEFAULT_TOOLCHAIN_PATHS)
+  }
+
+  pub fn manifest_patterns(&self) -> Vec<String> {
+    if self.manifest_patterns.is_empty() {
+      to_strings(DEFAULT_MANIFEST_PATTERNS)
+    } else {
+      self.manifest_patterns.clone()
+    }
+  }
+
+  pub fn docker_patterns(&self) -> Vec<String> {
+    if self.docker_patterns.is_empty() {
+      to_strings(DEFAULT_DOCKER_PATTERNS)
+    } else {
+      self.docker_patterns.clone()
+    }
+  }
+
+  pub fn python_patterns(&self) -> Vec<String> {
+    if self.python_patterns.is_empty() {
+      to_strings(DEFAULT_PYTHON_PATTERNS)
+    } else {
+      self.python_patterns.clone()
+    }
+  }
+
+  pub fn toolchain_patterns(&self) -> Vec<String> {
+    to_strings(DEFAULT_TOOLCHAIN_PATTERNS)
+  }
+}
+
+fn to_strings(values: &[&str]) -> Vec<String> {
+  values.iter().map(|value| value.to_string()).collect()
 }
 
 impl PartialEq for RepoConfig {
   fn eq(&self, other: &Self) -> bool {
-    self.name == other.name && self.provider == other.provider
+    self.name == other.name
+      && self.provider == other.provider
+      && self.host == other.host
+      && self.reference == other.reference
   }
 }
 impl Eq for RepoConfig {}
 
-#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Ord, PartialEq, PartialOrd, Serialize)]
+pub enum FileKind {
+  #[serde(rename = "manifest")]
+  Manifest,
+  #[serde(rename = "dockerfile")]
+  Dockerfile,
+  #[serde(rename = "lockfile")]
+  Lockfile,
+  // Python requirements, project or lock file
+  #[serde(rename = "python")]
+  Python,
+  // `rust-toolchain` or `rust-toolchain.toml` file
+  #[serde(rename = "toolchain")]
+  Toolchain,
+}
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub enum FileStatus {
+  // the file is new, or its content changed since the previous fetch
+  #[serde(rename = "updated")]
+  Updated,
+  #[default]
+  #[serde(rename = "unchanged")]
+  Unchanged,
+  // the content was stored by a previous version, without fetch information
+  #[serde(rename = "migrated")]
+  Migrated,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct FetchedFile {
+  pub path: String,
+  pub kind: FileKind,
+  // result of the last fetch of the file
+  #[serde(default)]
+  pub status: FileStatus,
+  // commit SHA the file was fetched at
+  #[serde(default)]
+  pub commit: Option<String>,
+  #[serde(default)]
+  pub fetched_at: Option<DateTime<Utc>>,
+  // blob SHA of the content, when provided by the provider
+  #[serde(default)]
+  pub sha: Option<String>,
+  // HTTP entity tag of the content, sent on next fetches to skip unchanged files
+  #[serde(default)]
+  pub etag: Option<String>,
+  pub content: String,
+}
+
+/// Abbreviate a commit SHA, other references (like the working tree of local repositories) are kept
+pub fn short_commit(commit: &str) -> &str {
+  if commit.chars().all(|c| c.is_ascii_hexdigit()) {
+    &commit[..commit.len().min(8)]
+  } else {
+    commit
+  }
+}
+
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
 pub enum Provider {
+  #[default]
   #[serde(rename = "github")]
   Github,
   #[serde(rename = "gitlab")]
   Gitlab,
+  #[serde(rename = "gitea")]
+  Gitea,
+  #[serde(rename = "bitbucket")]
+  Bitbucket,
+  #[serde(rename = "local")]
+  Local,
+  #[serde(rename = "git")]
+  Git,
 }
 
-impl Default for Provider {
-  fn default() -> Self {
-    Provider::Github
+impl Provider {
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      Provider::Github => "github",
+      Provider::Gitlab => "gitlab",
+      Provider::Gitea => "gitea",
+      Provider::Bitbucket => "bitbucket",
+      Provider::Local => "local",
+      Provider::Git => "git",
+    }
   }
 }
 
@@ -86,6 +385,10 @@ impl From<&str> for Provider {
     match value {
       "github" => Provider::Github,
       "gitlab" => Provider::Gitlab,
+      "gitea" => Provider::Gitea,
+      "bitbucket" => Provider::Bitbucket,
+      "local" => Provider::Local,
+      "git" => Provider::Git,
       _ => panic!("Invalid provider"),
     }
   }
@@ -0,0 +1,96 @@
+use reqwest::{header::InvalidHeaderValue, StatusCode};
+use std::{fmt, io, str::Utf8Error, string::FromUtf8Error};
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+#[derive(Debug)]
+pub enum Error {
+  // no token was given for the host, named by its command line argument
+  MissingToken(String, String),
+  InvalidToken,
+  Http(reqwest::Error),
+  // unexpected status of a request to the given URL
+  Status(String, StatusCode),
+  Decoding(String),
+  Io(io::Error),
+  Git(String),
+  // invalid Cargo manifest
+  Manifest(String),
+  // invalid glob pattern of the files to discover
+  Pattern(String),
+  // error reported by the provider API
+  Api(String),
+  // invalid content of the cache directory
+  Cache(String),
+  // invalid local checkout of the RustSec advisory database
+  AdvisoryDatabase(String),
+}
+
+impl fmt::Display for Error {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      Error::MissingToken(argument, host) => write!(f, "missing --{} for {}", argument, host),
+      Error::InvalidToken => write!(f, "invalid token"),
+      Error::Http(error) => write!(f, "HTTP error: {}", error),
+      Error::Status(url, status) => write!(f, "{} answered {}", url, status),
+      Error::Decoding(message) => write!(f, "unable to decode content: {}", message),
+      Error::Io(error) => write!(f, "{}", error),
+      Error::Git(message) => write!(f, "git error: {}", message),
+      Error::Manifest(message) => write!(f, "invalid manifest: {}", message),
+      Error::Pattern(message) => write!(f, "invalid pattern: {}", message),
+      Error::Api(message) => write!(f, "{}", message),
+      Error::Cache(message) => write!(f, "invalid cache: {}", message),
+      Error::AdvisoryDatabase(message) => write!(f, "invalid advisory database: {}", message),
+    }
+  }
+}
+
+impl std::error::Error for Error {}
+
+impl From<reqwest::Error> for Error {
+  fn from(error: reqwest::Error) -> Self {
+    Error::Http(error)
+  }
+}
+
+impl From<InvalidHeaderValue> for Error {
+  fn from(_error: InvalidHeaderValue) -> Self {
+    Error::InvalidToken
+  }
+}
+
+impl From<cargo_toml::Error> for Error {
+  fn from(error: cargo_toml::Error) -> Self {
+    Error::Manifest(error.to_string())
+  }
+}
+
+impl From<globset::Error> for Error {
+  fn from(error: globset::Error) -> Self {
+    Error::Pattern(error.to_string())
+  }
+}
+
+impl From<io::Error> for Error {
+  fn from(error: io::Error) -> Self {
+    Error::Io(error)
+  }
+}
+
+impl From<base64::DecodeError> for Error {
+  fn from(error: base64::DecodeError) -> Self {
+    Error::Decoding(error.to_string())
+  }
+}
+
+impl From<Utf8Error> for Error {
+  fn from(error: Utf8Error) -> Self {
+    Error::Decoding(error.to_string())
+  }
+}
+
+impl From<FromUtf8Error> for Error {
+  fn from(error: FromUtf8Error) -> Self {
+    Error::Decoding(error.to_string())
+  }
+}
@@ -0,0 +1,184 @@
+use crate::{
+  error::{Error, Result},
+  provider::{FileContent, FileResponse, SourceProvider, TreeFile},
+};
+use percent_encoding::{utf8_percent_encode, NON_ALPHANUMERIC};
+use std::{
+  collections::BTreeMap,
+  fs,
+  path::{Path, PathBuf},
+  process::Command,
+  sync::{Arc, Mutex},
+};
+
+// fetches of a cached repository share its FETCH_HEAD and shallow file, so they are locked per directory
+static FETCH_LOCKS: Mutex<BTreeMap<PathBuf, Arc<Mutex<()>>>> = Mutex::new(BTreeMap::new());
+
+/// Run a git command in a directory, returning its standard output on success
+pub fn run(directory: &Path, args: &[&str]) -> Result<String> {
+  let output = Command::new("git")
+    .current_dir(directory)
+    .args(args)
+    .output()?;
+
+  if output.status.success() {
+    Ok(String::from_utf8(output.stdout)?)
+  } else {
+    Err(Error::Git(
+      String::from_utf8_lossy(&output.stderr).trim().to_string(),
+    ))
+  }
+}
+
+/// Reject a repository or a reference which git would read as an option
+fn check_argument(kind: &str, value: &str) -> Result<()> {
+  if value.starts_with('-') {
+    return Err(Error::Git(format!("invalid {} {}", kind, value)));
+  }
+  Ok(())
+}
+
+/// Get a file at a commit of a git repository, `None` when the file does not exist
+pub fn show_file(directory: &Path, commit: &str, filename: &str) -> Result<Option<FileResponse>> {
+  check_argument("reference", commit)?;
+  let object = format!("{}:{}", commit, filename);
+
+  let sha = match run(
+    directory,
+    &[
+      "rev-parse",
+      "--verify",
+      "--quiet",
+      "--end-of-options",
+      &object,
+    ],
+  ) {
+    Ok(sha) => sha.trim().to_string(),
+    Err(_) => return Ok(None),
+  };
+  let content = run(directory, &["show", "--end-of-options", &object])?;
+
+  Ok(Some(FileResponse::Content(FileContent {
+    content,
+    sha: Some(sha),
+    etag: None,
+  })))
+}
+
+/// List the files at a commit of a git repository
+pub fn list_files(directory: &Path, commit: &str) -> Result<Vec<TreeFile>> {
+  check_argument("reference", commit)?;
+  let output = run(directory, &["ls-tree", "-r", "--end-of-options", commit])?;
+
+  let files = output
+    .lines()
+    .filter_map(|line| {
+      // each line is formatted as `<mode> <type> <sha>\t<path>`
+      let (object, path) = line.split_once('\t')?;
+      let object: Vec<&str> = object.split(' ').collect();
+
+      if object.get(1) == Some(&"blob") {
+        Some(TreeFile {
+          path: path.to_string(),
+          sha: object.get(2).map(|sha| sha.to_string()),
+        })
+      } else {
+        None
+      }
+    })
+    .collect();
+
+  Ok(files)
+}
+
+/// Resolve a reference of a git repository to a commit SHA
+pub fn rev_parse(directory: &Path, reference: &str) -> Result<String> {
+  check_argument("reference", reference)?;
+  let output = run(
+    directory,
+    &[
+      "rev-parse",
+      "--verify",
+      "--end-of-options",
+      &format!("{}^{{commit}}", reference),
+    ],
+  )?;
+  Ok(output.trim().to_string())
+}
+
+/// Provider of repositories reachable by a git URL, shallow-cloned into a cache directory
+pub struct Git {
+  directory: PathBuf,
+}
+
+/// Get the lock of the fetches of a cached repository
+fn fetch_lock(directory: &Path) -> Arc<Mutex<()>> {
+  let mut locks = FETCH_LOCKS
+    .lock()
+    .unwrap_or_else(|error| error.into_inner());
+  locks.entry(directory.to_path_buf()).or_default().clone()
+}
+
+impl Git {
+  pub fn new(cache_directory: &Path, url: &str) -> Result<Git> {
+    // URLs are percent-encoded, to name a single directory which no other URL shares
+    let name = utf8_percent_encode(url, NON_ALPHANUMERIC).to_string();
+    let directory = cache_directory.join(name);
+
+    if !directory.join("HEAD").exists() {
+      fs::create_dir_all(&directory)?;
+      run(&directory, &["init", "--bare", "--quiet"])?;
+    }
+
+    Ok(Git { directory })
+  }
+}
+
+impl SourceProvider for Git {
+  fn get_file(
+    &self,
+    _repository: &str,
+    reference: &str,
+    filename: &str,
+    _etag: Option<&str>,
+  ) -> Result<Option<FileResponse>> {
+    show_file(&self.directory, reference, filename)
+  }
+
+  fn list_tree(&self, _repository: &str, reference: &str) -> Result<Vec<TreeFile>> {
+    list_files(&self.directory, reference)
+  }
+
+  fn default_branch(&self, repository: &str) -> Result<String> {
+    check_argument("repository", repository)?;
+    let output = run(
+      &self.directory,
+      &["ls-remote", "--symref", "--", repository, "HEAD"],
+    )?;
+
+    output
+      .lines()
+      .find_map(|line| {
+        line
+          .strip_prefix("ref: refs/heads/")
+          .and_then(|line| line.split('\t').next())
+          .map(|branch| branch.to_string())
+      })
+      .ok_or_else(|| Error::Git(format!("no default branch found for {}", repository)))
+  }
+
+  fn commit_sha(&self, repository: &str, reference: &str) -> Result<String> {
+    check_argument("repository", repository)?;
+    check_argument("reference", reference)?;
+    let lock = fetch_lock(&self.directory);
+    let _lock = lock.lock().unwrap_or_else(|error| error.into_inner());
+
+    run(
+      &self.directory,
+      &[
+        "fetch", "--quiet", "--depth", "1", "--", repository, reference,
+      ],
+    )?;
+    rev_parse(&self.directory, "FETCH_HEAD")
+  }
+}
@@ -0,0 +1,154 @@
+use crate::{
+  error::{Error, Result},
+  http,
+  provider::{self, FileResponse, SourceProvider, TreeFile},
+  PROJECT_NAME,
+};
+use reqwest::{
+  blocking::Client,
+  header::{HeaderMap, HeaderValue, AUTHORIZATION, USER_AGENT},
+};
+use serde::de::DeserializeOwned;
+
+pub static DEFAULT_HOST: &str = "gitea.com";
+static TREE_PAGE_SIZE: usize = 1000;
+
+/// Get the API base URL of a Gitea (or Forgejo) host
+pub fn api_url(host: &str) -> String {
+  format!("https://{}/api/v1", host)
+}
+
+pub struct Gitea {
+  api_url: String,
+  client: Client,
+}
+
+impl Gitea {
+  pub fn new(api_url: &str, token: &str) -> Result<Gitea> {
+    let mut headers = HeaderMap::new();
+    headers.insert(
+      AUTHORIZATION,
+      HeaderValue::from_str(&format!("token {}", token))?,
+    );
+    headers.insert(USER_AGENT, HeaderValue::from_static(PROJECT_NAME));
+
+    let client = Client::builder().default_headers(headers).build()?;
+
+    Ok(Gitea {
+      api_url: api_url.trim_end_matches('/').to_string(),
+      client,
+    })
+  }
+
+  fn get<T: DeserializeOwned>(&self, endpoint: &str) -> Result<T> {
+    let url = format!("{}/{}", self.api_url, endpoint);
+    provider::json(http::send(self.client.get(&url))?)
+  }
+}
+
+impl SourceProvider for Gitea {
+  fn get_file(
+    &self,
+    repository: &str,
+    reference: &str,
+    filename: &str,
+    etag: Option<&str>,
+  ) -> Result<Option<FileResponse>> {
+    let (organization, repo_name) = provider::owner_and_name(repository)?;
+
+    let url = format!(
+      "{}/repos/{}/{}/raw/{}?ref={}",
+      self.api_url,
+      organization,
+      repo_name,
+      provider::encode_path(filename),
+      provider::encode(reference)
+    );
+
+    provider::request_file(self.client.get(&url), etag, |response| {
+      Ok((response.text()?, None))
+    })
+  }
+
+  fn list_tree(&self, repository: &str, reference: &str) -> Result<Vec<TreeFile>> {
+    let (organization, repo_name) = provider::owner_and_name(repository)?;
+
+    let mut paths = vec![];
+    let mut page = 1;
+    loop {
+      let response: Tree = self.get(&format!(
+        "repos/{}/{}/git/trees/{}?recursive=true&per_page={}&page={}",
+        organization,
+        repo_name,
+        provider::encode(reference),
+        TREE_PAGE_SIZE,
+        page
+      ))?;
+
+      paths.extend(
+        response
+          .tree
+          .into_iter()
+          .filter(|entry| entry._type == "blob")
+          .map(|entry| TreeFile {
+            path: entry.path,
+            sha: Some(entry.sha),
+          }),
+      );
+
+      if !response.truncated {
+        break;
+      }
+      page += 1;
+    }
+
+    Ok(paths)
+  }
+
+  fn default_branch(&self, repository: &str) -> Result<String> {
+    let (organization, repo_name) = provider::owner_and_name(repository)?;
+
+    let response: Repo = self.get(&format!("repos/{}/{}", organization, repo_name))?;
+    Ok(response.default_branch)
+  }
+
+  fn commit_sha(&self, repository: &str, reference: &str) -> Result<String> {
+    let (organization, repo_name) = provider::owner_and_name(repository)?;
+
+    let response: Vec<Commit> = self.get(&format!(
+      "repos/{}/{}/commits?sha={}&limit=1&stat=false",
+      organization,
+      repo_name,
+      provider::encode(reference)
+    ))?;
+    response
+      .into_iter()
+      .next()
+      .map(|commit| commit.sha)
+      .ok_or_else(|| Error::Api(format!("unknown reference {}", reference)))
+  }
+}
+
+#[derive(Debug, Deserialize)]
+struct Repo {
+  default_branch: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Commit {
+  sha: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Tree {
+  tree: Vec<TreeEntry>,
+  truncated: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct TreeEntry {
+  path: String,
+  #[serde(rename = "type")]
+  _type: String,
+  sha: String,
+}
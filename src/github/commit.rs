@@ -0,0 +1,4 @@
+#[derive(Debug, Deserialize)]
+pub struct Commit {
+  pub sha: String,
+}
@@ -0,0 +1,427 @@
+use crate::provider::{self, FileRequest, ResolveRequest, TreeFile};
+use std::collections::HashMap;
+
+// levels of the trees listed by the query resolving repositories, deeper trees are listed with the REST API
+static TREE_DEPTH: usize = 5;
+
+/// Get the GraphQL endpoint of a GitHub API, Enterprise Server instances serve it under `/api/graphql`
+pub fn url(api_url: &str) -> String {
+  match api_url.strip_suffix("/api/v3") {
+    Some(base_url) => format!("{}/api/graphql", base_url),
+    None => format!("{}/graphql", api_url),
+  }
+}
+
+/// Build a query of the blobs of several files, grouped by repository.
+/// Repositories are aliased `r<index>` and files `f<index>`, the index of the request.
+pub fn files_query(requests: &[FileRequest]) -> String {
+  let mut repositories: Vec<(&str, Vec<usize>)> = vec![];
+  for (index, request) in requests.iter().enumerate() {
+    match repositories
+      .iter_mut()
+      .find(|(repository, _)| *repository == request.repository)
+    {
+      Some((_, files)) => files.push(index),
+      None => repositories.push((request.repository, vec![index])),
+    }
+  }
+
+  let mut query = String::from("query {");
+  for (repository, files) in repositories {
+    // invalid names are left to GitHub, which fails the requests of their repository only
+    let (owner, name) = provider::owner_and_name(repository).unwrap_or(("", repository));
+    query.push_str(&format!(
+      " r{}: repository(owner: {}, name: {}) {{",
+      files[0],
+      string(owner),
+      string(name)
+    ));
+
+    for index in files {
+      let request = &requests[index];
+      let expression = format!("{}:{}", request.reference, request.path);
+      query.push_str(&format!(
+        " f{}: object(expression: {}) {{ ... on Blob {{ oid text isTruncated }} }}",
+        index,
+        string(&expression)
+      ));
+    }
+    query.push_str(" }");
+  }
+  query.push_str(" }");
+  query
+}
+
+/// Build a query of the commits and trees of several repositories, at their reference or their default branch.
+/// Repositories are aliased `r<index>`, the index of the request.
+pub fn resolve_query(requests: &[ResolveRequest]) -> String {
+  let mut query = String::from("query {");
+  for (index, request) in requests.iter().enumerate() {
+    let (owner, name) =
+      provider::owner_and_name(request.repository).unwrap_or(("", request.repository));
+    query.push_str(&format!(
+      " r{}: repository(owner: {}, name: {}) {{",
+      index,
+      string(owner),
+      string(name)
+    ));
+
+    match request.reference {
+      // annotated tags point to their commit
+      Some(reference) => query.push_str(&format!(
+        " object(expression: {}) {{ ...commit ... on Tag {{ target {{ ...commit }} }} }}",
+        string(reference)
+      )),
+      None => query.push_str(" defaultBranchRef { name target { ...commit } }"),
+    }
+    query.push_str(" }");
+  }
+  query.push_str(&format!(
+    " }} fragment commit on Commit {{ oid tree {{ {} }} }}",
+    entries(TREE_DEPTH)
+  ));
+  query
+}
+
+/// Select the entries of a tree, and those of its subtrees down to `depth` levels
+fn entries(depth: usize) -> String {
+  if depth <= 1 {
+    return "entries { path type oid }".to_string();
+  }
+  format!(
+    "entries {{ path type oid object {{ ... on Tree {{ {} }} }} }}",
+    entries(depth - 1)
+  )
+}
+
+/// Quote a GraphQL string, which escapes are the JSON ones
+fn string(value: &str) -> String {
+  serde_json::to_string(value).unwrap()
+}
+
+// objects of a repository by alias, `None` when the file does not exist
+type Repository = HashMap<String, Option<Blob>>;
+
+// data and errors of a query, the data of each repository being given by its alias
+#[derive(Debug, Deserialize)]
+pub struct Response<T = Repository> {
+  // `Option::default` does not require the data of repositories to implement `Default`
+  #[serde(default = "Option::default")]
+  pub data: Option<HashMap<String, Option<T>>>,
+  #[serde(default)]
+  pub errors: Vec<Error>,
+}
+
+impl<T> Response<T> {
+  /// Get the data of a repository by alias, `None` when it is not found
+  pub fn repository(&self, alias: &str) -> Option<&T> {
+    self.data.as_ref()?.get(alias)?.as_ref()
+  }
+
+  /// Get the error of a repository by alias, or of the whole query when it has no path
+  pub fn error(&self, alias: &str) -> Option<&Error> {
+    self
+      .errors
+      .iter()
+      .find(|error| error.path.first().and_then(|alias| alias.as_str()) == Some(alias))
+      .or_else(|| self.errors.iter().find(|error| error.path.is_empty()))
+  }
+}
+
+impl Response {
+  /// Get the blob of a request, `None` when the file does not exist, or the repository when it is not found
+  pub fn blob(&self, requests: &[FileRequest], index: usize) -> Option<&Blob> {
+    self
+      .data
+      .as_ref()?
+      .get(&repository_alias(requests, index))?
+      .as_ref()?
+      .get(&format!("f{}", index))?
+      .as_ref()
+  }
+
+  /// Whether the repository of a request was found
+  pub fn has_repository(&self, requests: &[FileRequest], index: usize) -> bool {
+    self
+      .repository(&repository_alias(requests, index))
+      .is_some()
+  }
+
+  /// Get the error of the repository of a request, or of the whole query when it has no path
+  pub fn repository_error(&self, requests: &[FileRequest], index: usize) -> Option<&Error> {
+    self.error(&repository_alias(requests, index))
+  }
+}
+
+/// Get the alias of the repository of a request, given by the index of its first request
+fn repository_alias(requests: &[FileRequest], index: usize) -> String {
+  let repository = requests
+    .iter()
+    .position(|request| request.repository == requests[index].repository)
+    .unwrap_or(index);
+  format!("r{}", repository)
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Blob {
+  // objects which are not blobs (like trees) have no field
+  #[serde(default)]
+  pub oid: Option<String>,
+  // missing for binary files
+  #[serde(default)]
+  pub text: Option<String>,
+  #[serde(default, rename = "isTruncated")]
+  pub is_truncated: bool,
+}
+
+/// Commit of the reference, or of the default branch, of a repository
+#[derive(Debug, Deserialize)]
+pub struct RepositoryCommit {
+  #[serde(default, rename = "defaultBranchRef")]
+  pub default_branch: Option<BranchRef>,
+  // object of the reference, `None` when it does not exist
+  #[serde(default)]
+  pub object: Option<GitObject>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BranchRef {
+  pub name: String,
+  pub target: GitObject,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GitObject {
+  // commits have an oid and a tree, annotated tags the commit they point to
+  #[serde(default)]
+  pub oid: Option<String>,
+  #[serde(default)]
+  pub tree: Option<Tree>,
+  #[serde(default)]
+  pub target: Option<Box<GitObject>>,
+}
+
+impl GitObject {
+  /// Get the SHA and the tree of the commit of the object, `None` when it is neither a commit nor an annotated tag
+  pub fn commit(&self) -> Option<(&str, &Tree)> {
+    match (&self.target, &self.oid, &self.tree) {
+      (Some(target), _, _) => target.commit(),
+      (None, Some(oid), Some(tree)) => Some((oid, tree)),
+      _ => None,
+    }
+  }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Tree {
+  // missing for the objects which are not trees, and for the trees deeper than the query
+  #[serde(default)]
+  pub entries: Option<Vec<TreeEntry>>,
+}
+
+impl Tree {
+  /// Get the files of the tree and its subtrees, `None` when a subtree is deeper than the query
+  pub fn files(&self) -> Option<Vec<TreeFile>> {
+    let mut files = vec![];
+    self.collect(&mut files)?;
+    Some(files)
+  }
+
+  fn collect(&self, files: &mut Vec<TreeFile>) -> Option<()> {
+    for entry in self.entries.as_ref()? {
+      match entry._type.as_str() {
+        "blob" => files.push(TreeFile {
+          path: entry.path.clone(),
+          sha: Some(entry.oid.clone()),
+        }),
+        "tree" => entry.object.as_ref()?.collect(files)?,
+        // submodules
+        _ => {}
+      }
+    }
+    Some(())
+  }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TreeEntry {
+  pub path: String,
+  #[serde(rename = "type")]
+  pub _type: String,
+  pub oid: String,
+  #[serde(default)]
+  pub object: Option<Tree>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Error {
+  pub message: String,
+  // aliases and indexes leading to the failing field, like `["r0", "f1"]`
+  #[serde(default)]
+  pub path: Vec<serde_json::Value>,
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn repository_errors() {
+    let requests = vec![
+      FileRequest {
+        repository: "media-cloud-ai/found",
+        reference: "main",
+        path: "Cargo.toml",
+      },
+      FileRequest {
+        repository: "media-cloud-ai/missing",
+        reference: "main",
+        path: "Cargo.toml",
+      },
+      FileRequest {
+        repository: "media-cloud-ai/private",
+        reference: "main",
+        path: "Cargo.toml",
+      },
+      FileRequest {
+        repository: "media-cloud-ai/private",
+        reference: "main",
+        path: "Dockerfile",
+      },
+    ];
+    let response: Response = serde_json::from_str(
+      r#"{
+        "data": { "r0": { "f0": null }, "r1": null, "r2": null },
+        "errors": [
+          { "message": "Could not resolve to a Repository named private", "path": ["r2"] },
+          { "message": "Could not resolve to a Repository named missing", "path": ["r1"] }
+        ]
+      }"#,
+    )
+    .unwrap();
+
+    let cases = vec![
+      (0, true, None),
+      (
+        1,
+        false,
+        Some("Could not resolve to a Repository named missing"),
+      ),
+      (
+        2,
+        false,
+        Some("Could not resolve to a Repository named private"),
+      ),
+      (
+        3,
+        false,
+        Some("Could not resolve to a Repository named private"),
+      ),
+    ];
+    for (index, found, error) in cases {
+      assert_eq!(
+        response.has_repository(&requests, index),
+        found,
+        "{}",
+        index
+      );
+      assert_eq!(
+        response
+          .repository_error(&requests, index)
+          .map(|error| error.message.as_str()),
+        error,
+        "{}",
+        index
+      );
+    }
+
+    let response: Response =
+      serde_json::from_str(r#"{ "errors": [{ "message": "Bad credentials" }] }"#).unwrap();
+    assert_eq!(
+      response
+        .repository_error(&requests, 1)
+        .map(|error| error.message.as_str()),
+      Some("Bad credentials")
+    );
+  }
+
+  #[test]
+  fn resolve_queries() {
+    let requests = vec![
+      ResolveRequest {
+        repository: "media-cloud-ai/mcai_worker_sdk",
+        reference: None,
+      },
+      ResolveRequest {
+        repository: "media-cloud-ai/rs_command_line_worker",
+        reference: Some("v1.0.0"),
+      },
+    ];
+    let query = resolve_query(&requests);
+
+    assert!(query.starts_with(
+      "query { r0: repository(owner: \"media-cloud-ai\", name: \"mcai_worker_sdk\") { defaultBranchRef { name target { ...commit } } }"
+    ));
+    assert!(query.contains(
+      " r1: repository(owner: \"media-cloud-ai\", name: \"rs_command_line_worker\") { object(expression: \"v1.0.0\") { ...commit ... on Tag { target { ...commit } } } }"
+    ));
+    assert!(query.contains(" } fragment commit on Commit { oid tree { entries { path type oid object { ... on Tree { entries {"));
+    assert_eq!(query.matches("entries").count(), TREE_DEPTH);
+  }
+
+  #[test]
+  fn resolved_commits() {
+    let response: Response<RepositoryCommit> = serde_json::from_str(
+      r#"{
+        "data": {
+          "r0": { "defaultBranchRef": { "name": "main", "target": { "oid": "c0", "tree": { "entries": [
+            { "path": "Cargo.toml", "type": "blob", "oid": "b0", "object": {} },
+            { "path": "docker", "type": "tree", "oid": "t0", "object": { "entries": [
+              { "path": "docker/Dockerfile", "type": "blob", "oid": "b1" }
+            ] } },
+            { "path": "sdk", "type": "commit", "oid": "s0", "object": {} }
+          ] } } } },
+          "r1": { "object": { "target": { "oid": "c1", "tree": { "entries": [] } } } },
+          "r2": { "object": { "oid": "c2", "tree": { "entries": [
+            { "path": "deep", "type": "tree", "oid": "t1" }
+          ] } } },
+          "r3": { "object": null }
+        }
+      }"#,
+    )
+    .unwrap();
+
+    let branch = response
+      .repository("r0")
+      .unwrap()
+      .default_branch
+      .as_ref()
+      .unwrap();
+    assert_eq!(branch.name, "main");
+    let (commit, tree) = branch.target.commit().unwrap();
+    assert_eq!(commit, "c0");
+    let files: Vec<(String, Option<String>)> = tree
+      .files()
+      .unwrap()
+      .into_iter()
+      .map(|file| (file.path, file.sha))
+      .collect();
+    assert_eq!(
+      files,
+      vec![
+        ("Cargo.toml".to_string(), Some("b0".to_string())),
+        ("docker/Dockerfile".to_string(), Some("b1".to_string())),
+      ]
+    );
+
+    // annotated tags resolve to their commit
+    let object = response.repository("r1").unwrap().object.as_ref().unwrap();
+    assert_eq!(object.commit().unwrap().0, "c1");
+
+    // trees deeper than the query are not listed
+    let object = response.repository("r2").unwrap().object.as_ref().unwrap();
+    assert!(object.commit().unwrap().1.files().is_none());
+
+    assert!(response.repository("r3").unwrap().object.is_none());
+  }
+}
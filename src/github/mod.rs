@@ -1,56 +1,224 @@
+mod commit;
 mod content;
-mod license;
-mod owner;
-mod permissions;
+mod graphql;
 mod repo;
+mod tree;
 
+use crate::{
+  error::{Error, Result},
+  http,
+  provider::{
+    self, FileContent, FileRequest, FileResponse, ResolveRequest, Resolved, SourceProvider,
+    TreeFile,
+  },
+  PROJECT_NAME,
+};
+use commit::Commit;
 use content::Content;
-use license::License;
-use owner::Owner;
-use permissions::Permissions;
+use repo::Repo;
+use reqwest::{
+  blocking::Client,
+  header::{HeaderMap, HeaderValue, ACCEPT, AUTHORIZATION, USER_AGENT},
+};
+use serde::de::DeserializeOwned;
 use std::str;
+use tree::Tree;
 
-use github_rs::client::{Executor, Github as GithubClient};
+pub static DEFAULT_HOST: &str = "github.com";
+
+/// Get the API base URL of a GitHub host, Enterprise Server instances serve it under `/api/v3`
+pub fn api_url(host: &str) -> String {
+  if host == DEFAULT_HOST {
+    "https://api.github.com".to_string()
+  } else {
+    format!("https://{}/api/v3", host)
+  }
+}
 
 pub struct Github {
-  client: GithubClient,
+  api_url: String,
+  client: Client,
+  // repositories of the same API fetched with the same token are batched together
+  batch_key: String,
 }
 
 impl Github {
-  pub fn new(token: &str) -> Github {
-    let client = GithubClient::new(token).unwrap();
+  pub fn new(api_url: &str, token: &str) -> Result<Github> {
+    let mut headers = HeaderMap::new();
+    headers.insert(
+      AUTHORIZATION,
+      HeaderValue::from_str(&format!("token {}", token))?,
+    );
+    headers.insert(
+      ACCEPT,
+      HeaderValue::from_static("application/vnd.github.v3+json"),
+    );
+    headers.insert(USER_AGENT, HeaderValue::from_static(PROJECT_NAME));
+
+    let client = Client::builder().default_headers(headers).build()?;
+
+    let api_url = api_url.trim_end_matches('/').to_string();
+    Ok(Github {
+      batch_key: format!("github {} {}", api_url, token),
+      api_url,
+      client,
+    })
+  }
+
+  fn get<T: DeserializeOwned>(&self, endpoint: &str) -> Result<T> {
+    let url = format!("{}/{}", self.api_url, endpoint);
+    provider::json(http::send(self.client.get(&url))?)
+  }
+}
+
+impl SourceProvider for Github {
+  fn get_file(
+    &self,
+    repository: &str,
+    reference: &str,
+    filename: &str,
+    etag: Option<&str>,
+  ) -> Result<Option<FileResponse>> {
+    let (organization, repo_name) = provider::owner_and_name(repository)?;
 
-    Github { client }
+    let url = format!(
+      "{}/repos/{}/{}/contents/{}?ref={}",
+      self.api_url, organization, repo_name, filename, reference
+    );
+
+    provider::request_file(self.client.get(&url), etag, |response| {
+      let response: Content = response.json()?;
+      let content = base64::decode(response.content.replace("\n", ""))?;
+      Ok((str::from_utf8(&content)?.to_string(), Some(response.sha)))
+    })
   }
 
-  pub fn get_file_content(&self, repository: &str, filename: &str) -> Option<String> {
-    let (organization, repo_name) = self.get_repo_information(repository);
+  fn list_tree(&self, repository: &str, reference: &str) -> Result<Vec<TreeFile>> {
+    let (organization, repo_name) = provider::owner_and_name(repository)?;
+
+    let response: Tree = self.get(&format!(
+      "repos/{}/{}/git/trees/{}?recursive=1",
+      organization, repo_name, reference
+    ))?;
 
-    let (_, status, response) = self
-      .client
-      .get()
-      .repos()
-      .owner(&organization)
-      .repo(&repo_name)
-      .contents()
-      .path(filename)
-      .execute::<serde_json::Value>()
-      .unwrap();
+    let paths = response
+      .tree
+      .into_iter()
+      .filter(|entry| entry._type == "blob")
+      .map(|entry| TreeFile {
+        path: entry.path,
+        sha: Some(entry.sha),
+      })
+      .collect();
 
-    if status == 200 {
-      let response: Content = serde_json::from_value(response.unwrap()).unwrap();
-      let content = base64::decode(response.content.replace("\n", "")).unwrap();
-      Some(str::from_utf8(&content).unwrap().to_string())
-    } else {
-      None
-    }
+    Ok(paths)
   }
 
-  fn get_repo_information(&self, repository: &str) -> (String, String) {
-    let repository = repository.split('/').collect::<Vec<&str>>();
+  fn default_branch(&self, repository: &str) -> Result<String> {
+    let (organization, repo_name) = provider::owner_and_name(repository)?;
 
-    let organization = repository[0].to_string();
-    let name = repository[1].to_string();
-    (organization, name)
+    let response: Repo = self.get(&format!("repos/{}/{}", organization, repo_name))?;
+    Ok(response.default_branch)
   }
+
+  fn commit_sha(&self, repository: &str, reference: &str) -> Result<String> {
+    let (organization, repo_name) = provider::owner_and_name(repository)?;
+
+    let response: Commit = self.get(&format!(
+      "repos/{}/{}/commits/{}",
+      organization, repo_name, reference
+    ))?;
+    Ok(response.sha)
+  }
+
+  fn batch_key(&self) -> Option<String> {
+    Some(self.batch_key.clone())
+  }
+
+  fn resolve_all(&self, requests: &[ResolveRequest]) -> Result<Vec<Result<Resolved>>> {
+    let query = Query {
+      query: graphql::resolve_query(requests),
+    };
+    let request = self.client.post(&graphql::url(&self.api_url)).json(&query);
+    let response: graphql::Response<graphql::RepositoryCommit> =
+      provider::json(http::send(request)?)?;
+
+    let resolutions = requests.iter().enumerate().map(|(index, request)| {
+      let alias = format!("r{}", index);
+      let repository = response.repository(&alias).ok_or_else(|| {
+        let message = response
+          .error(&alias)
+          .map(|error| error.message.clone())
+          .unwrap_or_else(|| format!("{} not found", request.repository));
+        Error::Api(message)
+      })?;
+
+      let (reference, object) = match (request.reference, &repository.default_branch) {
+        (Some(reference), _) => (reference.to_string(), repository.object.as_ref()),
+        (None, Some(branch)) => (branch.name.clone(), Some(&branch.target)),
+        (None, None) => {
+          return Err(Error::Api(format!(
+            "{} has no default branch",
+            request.repository
+          )))
+        }
+      };
+      let (commit, tree) = object
+        .and_then(|object| object.commit())
+        .ok_or_else(|| Error::Api(format!("{} not found in {}", reference, request.repository)))?;
+
+      // trees deeper than the query are listed with the REST API
+      let tree = tree
+        .files()
+        .or_else(|| self.list_tree(request.repository, commit).ok());
+
+      Ok(Resolved {
+        reference,
+        commit: commit.to_string(),
+        tree,
+      })
+    });
+
+    Ok(resolutions.collect())
+  }
+
+  fn get_files(&self, requests: &[FileRequest]) -> Result<Vec<Result<Option<FileResponse>>>> {
+    let query = Query {
+      query: graphql::files_query(requests),
+    };
+    let request = self.client.post(&graphql::url(&self.api_url)).json(&query);
+    let response: graphql::Response = provider::json(http::send(request)?)?;
+
+    let responses = requests.iter().enumerate().map(|(index, request)| {
+      if !response.has_repository(requests, index) {
+        let message = response
+          .repository_error(requests, index)
+          .map(|error| error.message.clone())
+          .unwrap_or_else(|| format!("{} not found", request.repository));
+        return Err(Error::Api(message));
+      }
+
+      match response.blob(requests, index) {
+        None => Ok(None),
+        Some(graphql::Blob {
+          oid: Some(oid),
+          text: Some(text),
+          is_truncated: false,
+        }) => Ok(Some(FileResponse::Content(FileContent {
+          content: text.clone(),
+          sha: Some(oid.clone()),
+          etag: None,
+        }))),
+        // binary, truncated or unexpected objects are requested through the REST API
+        Some(_) => self.get_file(request.repository, request.reference, request.path, None),
+      }
+    });
+
+    Ok(responses.collect())
+  }
+}
+
+#[derive(Serialize)]
+struct Query {
+  query: String,
 }
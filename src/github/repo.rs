@@ -1,79 +1,4 @@
-use super::{License, Owner, Permissions};
-
 #[derive(Debug, Deserialize)]
 pub struct Repo {
-  pub archive_url: String,
-  pub archived: bool,
-  pub assignees_url: String,
-  pub blobs_url: String,
-  pub branches_url: String,
-  pub clone_url: String,
-  pub collaborators_url: String,
-  pub comments_url: String,
-  pub commits_url: String,
-  pub compare_url: String,
-  pub contents_url: String,
-  pub contributors_url: String,
-  pub created_at: String,
   pub default_branch: String,
-  pub deployments_url: String,
-  pub description: Option<String>,
-  pub disabled: bool,
-  pub downloads_url: String,
-  pub events_url: String,
-  pub fork: bool,
-  pub forks: usize,
-  pub forks_count: usize,
-  pub forks_url: String,
-  pub full_name: String,
-  pub git_commits_url: String,
-  pub git_refs_url: String,
-  pub git_tags_url: String,
-  pub git_url: String,
-  pub has_downloads: bool,
-  pub has_issues: bool,
-  pub has_pages: bool,
-  pub has_projects: bool,
-  pub has_wiki: bool,
-  pub homepage: Option<String>,
-  pub hooks_url: String,
-  pub html_url: String,
-  pub id: usize,
-  pub issue_comment_url: String,
-  pub issue_events_url: String,
-  pub issues_url: String,
-  pub keys_url: String,
-  pub labels_url: String,
-  pub language: Option<String>,
-  pub languages_url: String,
-  pub license: Option<License>,
-  pub merges_url: String,
-  pub milestones_url: String,
-  pub mirror_url: Option<String>,
-  pub name: String,
-  pub node_id: String,
-  pub notifications_url: String,
-  pub open_issues: usize,
-  pub open_issues_count: usize,
-  pub owner: Owner,
-  pub permissions: Permissions,
-  pub private: bool,
-  pub pulls_url: String,
-  pub pushed_at: String,
-  pub releases_url: String,
-  pub size: usize,
-  pub ssh_url: String,
-  pub stargazers_count: usize,
-  pub stargazers_url: String,
-  pub statuses_url: String,
-  pub subscribers_url: String,
-  pub subscription_url: String,
-  pub svn_url: String,
-  pub tags_url: String,
-  pub teams_url: String,
-  pub trees_url: String,
-  pub updated_at: String,
-  pub url: String,
-  pub watchers: usize,
-  pub watchers_count: usize,
 }
@@ -0,0 +1,12 @@
+#[derive(Debug, Deserialize)]
+pub struct Tree {
+  pub tree: Vec<TreeEntry>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TreeEntry {
+  pub path: String,
+  #[serde(rename = "type")]
+  pub _type: String,
+  pub sha: String,
+}
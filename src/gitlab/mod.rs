@@ -1,67 +1,159 @@
-use gitlab::Gitlab as GitlabClient;
+use crate::{
+  error::{Error, Result},
+  http,
+  provider::{self, FileResponse, SourceProvider, TreeFile},
+  PROJECT_NAME,
+};
+use percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use reqwest::{
+  blocking::Client,
+  header::{HeaderMap, HeaderValue, USER_AGENT},
+};
 use std::str;
 
+pub static DEFAULT_HOST: &str = "gitlab.com";
+static TREE_PAGE_SIZE: usize = 100;
+
+// characters kept as is in path segments, `/` of namespaces and nested files is encoded
+const PATH_SEGMENT: &AsciiSet = &NON_ALPHANUMERIC.remove(b'-').remove(b'.').remove(b'_');
+
+/// Get the API base URL of a GitLab host
+pub fn api_url(host: &str) -> String {
+  format!("https://{}/api/v4", host)
+}
+
 pub struct Gitlab {
-  token: String,
-  client: GitlabClient,
+  api_url: String,
+  client: Client,
 }
 
 impl Gitlab {
-  pub fn new(token: &str) -> Gitlab {
-    let client = GitlabClient::new("gitlab.com", token).unwrap();
+  pub fn new(api_url: &str, token: &str) -> Result<Gitlab> {
+    let mut headers = HeaderMap::new();
+    headers.insert("PRIVATE-TOKEN", HeaderValue::from_str(token)?);
+    headers.insert(USER_AGENT, HeaderValue::from_static(PROJECT_NAME));
+
+    let client = Client::builder().default_headers(headers).build()?;
 
-    Gitlab {
+    Ok(Gitlab {
+      api_url: api_url.trim_end_matches('/').to_string(),
       client,
-      token: token.to_string(),
-    }
+    })
   }
 
-  pub fn get_file_content(&self, repository: &str, filename: &str) -> Option<String> {
-    use {
-      gitlab::api::{projects, Query},
-      reqwest::{
-        blocking::Client,
-        header::{HeaderMap, HeaderValue},
-      },
-    };
-
-    let endpoint = projects::Project::builder()
-      .project(repository)
-      .build()
-      .unwrap();
+  fn get_project(&self, repository: &str) -> Result<Project> {
+    provider::json(http::send(self.client.get(&self.project_url(repository)))?)
+  }
 
-    let project: Project = endpoint.query(&self.client).unwrap();
+  /// Get the URL of a project, identified by its URL-encoded path
+  fn project_url(&self, repository: &str) -> String {
+    format!(
+      "{}/projects/{}",
+      self.api_url,
+      utf8_percent_encode(repository, PATH_SEGMENT)
+    )
+  }
+}
 
+impl SourceProvider for Gitlab {
+  fn get_file(
+    &self,
+    repository: &str,
+    reference: &str,
+    filename: &str,
+    etag: Option<&str>,
+  ) -> Result<Option<FileResponse>> {
     let url = format!(
-      "https://gitlab.com/api/v4/projects/{}/repository/files/{}?ref=master",
-      project.id, filename
+      "{}/repository/files/{}",
+      self.project_url(repository),
+      utf8_percent_encode(filename, PATH_SEGMENT)
     );
+    let request = self.client.get(&url).query(&[("ref", reference)]);
 
-    let mut headers = HeaderMap::new();
-    headers.insert("PRIVATE-TOKEN", HeaderValue::from_str(&self.token).unwrap());
+    provider::request_file(request, etag, |response| {
+      let response: RepositoryFile = response.json()?;
+      let content = base64::decode(response.content)?;
+      Ok((
+        str::from_utf8(&content)?.to_string(),
+        Some(response.blob_id),
+      ))
+    })
+  }
+
+  fn list_tree(&self, repository: &str, reference: &str) -> Result<Vec<TreeFile>> {
+    let url = format!("{}/repository/tree", self.project_url(repository));
+
+    let mut paths = vec![];
+    let mut page = 1;
+    loop {
+      let request = self.client.get(&url).query(&[
+        ("recursive", "true"),
+        ("ref", reference),
+        ("per_page", &TREE_PAGE_SIZE.to_string()),
+        ("page", &page.to_string()),
+      ]);
 
-    let client = Client::builder().default_headers(headers).build().unwrap();
+      let entries: Vec<TreeEntry> = provider::json(http::send(request)?)?;
+      let count = entries.len();
 
-    if let Ok(response) = client.get(&url).send().unwrap().json::<FileResponse>() {
-      let content = base64::decode(response.content).unwrap();
-      return Some(str::from_utf8(&content).unwrap().to_string());
+      paths.extend(
+        entries
+          .into_iter()
+          .filter(|entry| entry._type == "blob")
+          .map(|entry| TreeFile {
+            path: entry.path,
+            sha: Some(entry.id),
+          }),
+      );
+
+      if count < TREE_PAGE_SIZE {
+        break;
+      }
+      page += 1;
     }
 
-    None
+    Ok(paths)
+  }
+
+  fn default_branch(&self, repository: &str) -> Result<String> {
+    self
+      .get_project(repository)?
+      .default_branch
+      .ok_or_else(|| Error::Api(format!("{} has no default branch", repository)))
+  }
+
+  fn commit_sha(&self, repository: &str, reference: &str) -> Result<String> {
+    let url = format!(
+      "{}/repository/commits/{}",
+      self.project_url(repository),
+      utf8_percent_encode(reference, PATH_SEGMENT)
+    );
+
+    let commit: Commit = provider::json(http::send(self.client.get(&url))?)?;
+    Ok(commit.id)
   }
 }
 
 #[derive(Debug, Deserialize)]
 struct Project {
-  name: String,
-  id: usize,
+  default_branch: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Commit {
+  id: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct TreeEntry {
+  id: String,
+  path: String,
+  #[serde(rename = "type")]
+  _type: String,
 }
 
 #[derive(Debug, Deserialize)]
-struct FileResponse {
-  file_name: String,
-  file_path: String,
-  size: usize,
-  encoding: String,
+struct RepositoryFile {
   content: String,
+  blob_id: String,
 }
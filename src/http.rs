@@ -0,0 +1,172 @@
+use crate::error::Result;
+use chrono::{DateTime, Duration as ChronoDuration, TimeZone, Utc};
+use reqwest::{
+  blocking::{RequestBuilder, Response},
+  header::{HeaderMap, RETRY_AFTER},
+  StatusCode, Url,
+};
+use std::{collections::BTreeMap, sync::Mutex, thread, time::Duration};
+
+static MAX_RETRIES: u32 = 5;
+static INITIAL_BACKOFF: Duration = Duration::from_millis(500);
+static MAX_BACKOFF: Duration = Duration::from_secs(60);
+// prefixes of the rate limit headers, as sent by GitHub and Bitbucket, or GitLab
+static RATE_LIMIT_PREFIXES: &[&str] = &["x-ratelimit-", "ratelimit-"];
+// reset values below are a number of seconds to wait, above are UNIX timestamps
+static RESET_TIMESTAMP_THRESHOLD: i64 = 1_000_000_000;
+
+/// Request quota of a host, as reported by its last response
+#[derive(Clone, Debug)]
+pub struct RateLimit {
+  pub limit: Option<u64>,
+  pub remaining: u64,
+  pub reset: Option<DateTime<Utc>>,
+}
+
+// quota of each host, shared by the providers of all repositories
+static RATE_LIMITS: Mutex<BTreeMap<String, RateLimit>> = Mutex::new(BTreeMap::new());
+
+/// Send a request, waiting for the reset of the quota of the host when it is exhausted.
+/// Rate limited requests, server errors, timeouts and connection errors are retried with exponential backoff.
+pub fn send(request: RequestBuilder) -> Result<Response> {
+  let quota = request
+    .try_clone()
+    .and_then(|request| request.build().ok())
+    .map(|request| quota_name(request.url()))
+    .unwrap_or_default();
+
+  let mut attempt = 0;
+  loop {
+    wait_for_reset(&quota);
+
+    // requests with a streamed body can not be sent twice
+    let current = match request.try_clone() {
+      Some(current) if attempt < MAX_RETRIES => current,
+      _ => return Ok(request.send()?),
+    };
+
+    let delay = match current.send() {
+      Ok(response) => {
+        update_rate_limit(&quota, response.headers());
+        match retry_delay(&response, attempt) {
+          Some(delay) => delay,
+          None => return Ok(response),
+        }
+      }
+      Err(error) if error.is_timeout() || error.is_connect() => backoff(attempt),
+      Err(error) => return Err(error.into()),
+    };
+
+    thread::sleep(delay);
+    attempt += 1;
+  }
+}
+
+/// Get the last known quota of each host
+pub fn rate_limits() -> Vec<(String, RateLimit)> {
+  RATE_LIMITS
+    .lock()
+    .unwrap_or_else(|error| error.into_inner())
+    .iter()
+    .map(|(host, rate_limit)| (host.clone(), rate_limit.clone()))
+    .collect()
+}
+
+/// Get the name of the quota of a URL, the host with the GraphQL API counted apart
+fn quota_name(url: &Url) -> String {
+  let host = url.host_str().unwrap_or_default();
+  if url.path().ends_with("/graphql") {
+    format!("{} (GraphQL)", host)
+  } else {
+    host.to_string()
+  }
+}
+
+fn backoff(attempt: u32) -> Duration {
+  INITIAL_BACKOFF
+    .checked_mul(2u32.saturating_pow(attempt))
+    .map_or(MAX_BACKOFF, |backoff| backoff.min(MAX_BACKOFF))
+}
+
+/// Get the delay before retrying a request, `None` when the response is final
+fn retry_delay(response: &Response, attempt: u32) -> Option<Duration> {
+  let status = response.status();
+  let exhausted = header(response.headers(), "remaining") == Some(0);
+
+  let transient = status == StatusCode::TOO_MANY_REQUESTS
+    || (status == StatusCode::FORBIDDEN && exhausted)
+    || status.is_server_error();
+  if !transient {
+    return None;
+  }
+
+  // when the quota is exhausted, the next attempt waits for its reset
+  let retry_after = response
+    .headers()
+    .get(RETRY_AFTER)
+    .and_then(|value| value.to_str().ok())
+    .and_then(|value| value.parse().ok())
+    .map(Duration::from_secs);
+
+  Some(retry_after.unwrap_or_else(|| backoff(attempt)))
+}
+
+fn header(headers: &HeaderMap, name: &str) -> Option<i64> {
+  RATE_LIMIT_PREFIXES.iter().find_map(|prefix| {
+    headers
+      .get(format!("{}{}", prefix, name).as_str())
+      .and_then(|value| value.to_str().ok())
+      .and_then(|value| value.trim().parse().ok())
+  })
+}
+
+fn update_rate_limit(host: &str, headers: &HeaderMap) {
+  let remaining = match header(headers, "remaining") {
+    Some(remaining) => remaining.max(0) as u64,
+    None => return,
+  };
+
+  let reset = header(headers, "reset").and_then(|reset| {
+    if reset < RESET_TIMESTAMP_THRESHOLD {
+      Some(Utc::now() + ChronoDuration::seconds(reset))
+    } else {
+      Utc.timestamp_opt(reset, 0).single()
+    }
+  });
+
+  let rate_limit = RateLimit {
+    limit: header(headers, "limit").map(|limit| limit.max(0) as u64),
+    remaining,
+    reset,
+  };
+
+  RATE_LIMITS
+    .lock()
+    .unwrap_or_else(|error| error.into_inner())
+    .insert(host.to_string(), rate_limit);
+}
+
+/// Wait until the reset of the quota of a host, when it is exhausted
+fn wait_for_reset(host: &str) {
+  let reset = match RATE_LIMITS
+    .lock()
+    .unwrap_or_else(|error| error.into_inner())
+    .get(host)
+  {
+    Some(RateLimit {
+      remaining: 0,
+      reset: Some(reset),
+      ..
+    }) => *reset,
+    _ => return,
+  };
+
+  if let Ok(delay) = (reset - Utc::now()).to_std() {
+    eprintln!(
+      "Rate limit of {} exhausted, waiting {}s for its reset",
+      host,
+      delay.as_secs() + 1
+    );
+    thread::sleep(delay + Duration::from_secs(1));
+  }
+}
@@ -0,0 +1,204 @@
+use crate::{
+  error::{Error, Result},
+  http, PROJECT_NAME,
+};
+use reqwest::{
+  blocking::Client,
+  header::{HeaderMap, HeaderValue, USER_AGENT},
+  StatusCode,
+};
+use semver::Version;
+use std::{fs, io, path::PathBuf};
+
+pub static DEFAULT_INDEX: &str = "sparse+https://index.crates.io/";
+
+/// Registry index of crates, in the layout of crates.io
+pub enum Index {
+  // index served over HTTP, like `sparse+https://index.crates.io/`
+  Sparse { url: String, client: Client },
+  // local checkout of a git index, like a clone of `https://github.com/rust-lang/crates.io-index`
+  Local(PathBuf),
+}
+
+/// Published version of a crate, as a line of its index file
+#[derive(Debug, Deserialize)]
+struct IndexEntry {
+  vers: String,
+  #[serde(default)]
+  yanked: bool,
+}
+
+impl Index {
+  /// Open an index from its URL, served over HTTP (`sparse+https://`, `https://`) or a local directory (`file://` or a path)
+  pub fn new(url: &str) -> Result<Index> {
+    let sparse_url = url.strip_prefix("sparse+").unwrap_or(url);
+    if sparse_url.starts_with("http://") || sparse_url.starts_with("https://") {
+      let mut headers = HeaderMap::new();
+      headers.insert(USER_AGENT, HeaderValue::from_static(PROJECT_NAME));
+      let client = Client::builder().default_headers(headers).build()?;
+
+      return Ok(Index::Sparse {
+        url: format!("{}/", sparse_url.trim_end_matches('/')),
+        client,
+      });
+    }
+
+    let path = PathBuf::from(url.strip_prefix("file://").unwrap_or(url));
+    if !path.is_dir() {
+      return Err(Error::Io(io::Error::new(
+        io::ErrorKind::NotFound,
+        format!("index directory {} not found", path.display()),
+      )));
+    }
+    Ok(Index::Local(path))
+  }
+
+  /// Get the newest published version of a crate, `None` when the crate is not in the index.
+  /// Yanked versions are ignored, and pre-releases are only considered without stable release.
+  pub fn latest_version(&self, name: &str) -> Result<Option<Version>> {
+    let versions: Vec<Version> = match self.entries(name)? {
+      Some(entries) => entries
+        .into_iter()
+        .filter(|entry| !entry.yanked)
+        .filter_map(|entry| Version::parse(&entry.vers).ok())
+        .collect(),
+      None => return Ok(None),
+    };
+
+    let stable = versions
+      .iter()
+      .filter(|version| !version.is_prerelease())
+      .max();
+    Ok(stable.or_else(|| versions.iter().max()).cloned())
+  }
+
+  /// Get the yanked versions of a crate, none when the crate is not in the index
+  pub fn yanked_versions(&self, name: &str) -> Result<Vec<Version>> {
+    Ok(
+      self
+        .entries(name)?
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|entry| entry.yanked)
+        .filter_map(|entry| Version::parse(&entry.vers).ok())
+        .collect(),
+    )
+  }
+
+  /// Get the published versions of a crate, `None` when the crate is not in the index
+  fn entries(&self, name: &str) -> Result<Option<Vec<IndexEntry>>> {
+    let content = match self {
+      Index::Sparse { url, client } => {
+        let url = format!("{}{}", url, path(name));
+        let response = http::send(client.get(&url))?;
+        match response.status() {
+          StatusCode::OK => response.text()?,
+          // unknown crates are answered as not found, or forbidden by some mirrors
+          StatusCode::NOT_FOUND | StatusCode::GONE | StatusCode::FORBIDDEN => return Ok(None),
+          status => return Err(Error::Status(url, status)),
+        }
+      }
+      Index::Local(directory) => match fs::read_to_string(directory.join(path(name))) {
+        Ok(content) => content,
+        Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(error) => return Err(error.into()),
+      },
+    };
+
+    Ok(Some(
+      content
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect(),
+    ))
+  }
+}
+
+/// Get the path of the file of a crate in the index, like `se/rd/serde` or `3/l/log`
+fn path(name: &str) -> String {
+  let name = name.to_lowercase();
+  match name.len() {
+    1 => format!("1/{}", name),
+    2 => format!("2/{}", name),
+    3 => format!("3/{}/{}", &name[..1], name),
+    _ => format!("{}/{}/{}", &name[..2], &name[2..4], name),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn crate_paths() {
+    let cases = vec![
+      ("a", "1/a"),
+      ("cc", "2/cc"),
+      ("log", "3/l/log"),
+      ("serde", "se/rd/serde"),
+      ("Inflector", "in/fl/inflector"),
+      ("mcai_worker_sdk", "mc/ai/mcai_worker_sdk"),
+    ];
+
+    for (name, expected) in cases {
+      assert_eq!(path(name), expected, "{}", name);
+    }
+  }
+
+  #[test]
+  fn local_index_versions() {
+    // fixture standing in for a local checkout of an index
+    let directory = std::env::temp_dir().join(format!("mcai-workers-index-{}", std::process::id()));
+    let crates = vec![
+      (
+        "serde",
+        vec![
+          r#"{"name":"serde","vers":"1.0.0"}"#,
+          r#"{"name":"serde","vers":"1.0.2","yanked":true}"#,
+          r#"{"name":"serde","vers":"1.0.1","yanked":false}"#,
+          r#"{"name":"serde","vers":"1.1.0-beta.1"}"#,
+          "not an entry",
+        ],
+      ),
+      (
+        "log",
+        vec![
+          r#"{"name":"log","vers":"0.1.0-alpha"}"#,
+          r#"{"name":"log","vers":"0.1.0-beta"}"#,
+        ],
+      ),
+      ("cc", vec![r#"{"name":"cc","vers":"1.0.0","yanked":true}"#]),
+    ];
+    for (name, entries) in crates {
+      let file = directory.join(path(name));
+      fs::create_dir_all(file.parent().unwrap()).unwrap();
+      fs::write(file, entries.join("\n")).unwrap();
+    }
+
+    let index = Index::new(&format!("file://{}", directory.display())).unwrap();
+    let version = |version| Some(Version::parse(version).unwrap());
+    let cases = vec![
+      ("serde", version("1.0.1")),
+      ("Serde", version("1.0.1")),
+      ("log", version("0.1.0-beta")),
+      ("cc", None),
+      ("tokio", None),
+    ];
+    for (name, expected) in cases {
+      assert_eq!(index.latest_version(name).unwrap(), expected, "{}", name);
+    }
+
+    assert_eq!(
+      index.yanked_versions("serde").unwrap(),
+      vec![Version::parse("1.0.2").unwrap()]
+    );
+    assert!(index.yanked_versions("tokio").unwrap().is_empty());
+
+    fs::remove_dir_all(directory).unwrap();
+  }
+
+  #[test]
+  fn missing_local_index() {
+    assert!(Index::new("/nonexistent/mcai-workers-index").is_err());
+  }
+}
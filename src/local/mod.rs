@@ -0,0 +1,119 @@
+use crate::{
+  error::{Error, Result},
+  git,
+  provider::{FileContent, FileResponse, SourceProvider, TreeFile},
+};
+use std::{
+  fs,
+  io::{self, ErrorKind},
+  path::{Path, PathBuf},
+};
+
+/// Reference of the files of a directory which is not a git repository
+pub static WORKING_TREE: &str = "working-tree";
+
+/// Provider of repositories from a directory on disk.
+/// Git repositories are read at the requested commit, other directories from their working tree.
+pub struct Local {
+  directory: PathBuf,
+}
+
+impl Local {
+  pub fn new(directory: &str) -> Local {
+    Local {
+      directory: PathBuf::from(directory),
+    }
+  }
+
+  fn is_git_repository(&self) -> bool {
+    git::run(&self.directory, &["rev-parse", "--is-inside-work-tree"]).is_ok()
+  }
+}
+
+impl SourceProvider for Local {
+  fn get_file(
+    &self,
+    _repository: &str,
+    reference: &str,
+    filename: &str,
+    _etag: Option<&str>,
+  ) -> Result<Option<FileResponse>> {
+    if reference == WORKING_TREE {
+      let content = match fs::read_to_string(self.directory.join(filename)) {
+        Ok(content) => content,
+        Err(error) if error.kind() == ErrorKind::NotFound => return Ok(None),
+        Err(error) => return Err(error.into()),
+      };
+
+      Ok(Some(FileResponse::Content(FileContent {
+        content,
+        sha: None,
+        etag: None,
+      })))
+    } else {
+      git::show_file(&self.directory, reference, filename)
+    }
+  }
+
+  fn list_tree(&self, _repository: &str, reference: &str) -> Result<Vec<TreeFile>> {
+    if reference == WORKING_TREE {
+      let mut paths = vec![];
+      list_directory(&self.directory, &self.directory, &mut paths)?;
+      Ok(paths)
+    } else {
+      git::list_files(&self.directory, reference)
+    }
+  }
+
+  fn default_branch(&self, _repository: &str) -> Result<String> {
+    if !self.is_git_repository() {
+      return Ok(WORKING_TREE.to_string());
+    }
+
+    git::run(&self.directory, &["symbolic-ref", "--short", "HEAD"])
+      .map(|branch| branch.trim().to_string())
+      .or_else(|_| Ok("HEAD".to_string()))
+  }
+
+  fn commit_sha(&self, _repository: &str, reference: &str) -> Result<String> {
+    if reference == WORKING_TREE {
+      if !self.directory.is_dir() {
+        return Err(Error::Io(io::Error::new(
+          ErrorKind::NotFound,
+          format!("directory {} not found", self.directory.display()),
+        )));
+      }
+
+      Ok(WORKING_TREE.to_string())
+    } else {
+      git::rev_parse(&self.directory, reference)
+    }
+  }
+}
+
+fn list_directory(root: &Path, directory: &Path, paths: &mut Vec<TreeFile>) -> std::io::Result<()> {
+  for entry in fs::read_dir(directory)? {
+    let path = entry?.path();
+
+    if path.is_dir() {
+      // skip git metadata and Cargo build outputs
+      if path
+        .file_name()
+        .is_some_and(|name| name != ".git" && name != "target")
+      {
+        list_directory(root, &path, paths)?;
+      }
+    } else if let Ok(relative_path) = path.strip_prefix(root) {
+      let components: Vec<String> = relative_path
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy().to_string())
+        .collect();
+      paths.push(TreeFile {
+        path: components.join("/"),
+        sha: None,
+      });
+    }
+  }
+
+  Ok(())
+}
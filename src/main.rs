@@ -2,32 +2,102 @@
 extern crate serde_derive;
 
 mod actions;
+mod audit;
+mod bitbucket;
+mod cache;
+mod cargo;
 mod config;
+mod error;
+mod git;
+mod gitea;
 mod github;
 mod gitlab;
+mod http;
+mod index;
+mod local;
+mod parallel;
+mod provider;
+mod python;
+mod report;
+mod rust;
+mod version;
 
-use cargo_toml::Manifest;
-use clap::{App, Arg, SubCommand};
-use dockerfile_parser::Dockerfile;
+use clap::{App, AppSettings, Arg, SubCommand};
 
 static PROJECT_NAME: &str = "mcai-workers";
+static PROVIDERS: &[&str] = &["github", "gitlab", "gitea", "bitbucket", "local", "git"];
 static OPEN_SOURCE_WORKERS_URL: &str = "https://raw.githubusercontent.com/media-cloud-ai/mcai_workers/master/workers/open_source_mcai_workers.json";
 
-#[derive(Debug)]
-pub struct Repository {
-  name: String,
-  cargo_manifest: Option<Manifest>,
-  dockerfile: Option<Dockerfile>,
-}
-
 fn main() {
   let github_token_arg = Arg::with_name("github-token")
     .long("github-token")
-    .env("GITHUB_TOKEN");
+    .env("GITHUB_TOKEN")
+    .multiple(true)
+    .number_of_values(1)
+    .use_delimiter(true)
+    .help("GitHub token, prefixed by its host for Enterprise Server instances (e.g. github.example.com=TOKEN)");
 
   let gitlab_token_arg = Arg::with_name("gitlab-token")
     .long("gitlab-token")
-    .env("GITLAB_TOKEN");
+    .env("GITLAB_TOKEN")
+    .multiple(true)
+    .number_of_values(1)
+    .use_delimiter(true)
+    .help("GitLab token, prefixed by its host for self-hosted instances (e.g. gitlab.example.com=TOKEN)");
+
+  let gitea_token_arg = Arg::with_name("gitea-token")
+    .long("gitea-token")
+    .env("GITEA_TOKEN")
+    .multiple(true)
+    .number_of_values(1)
+    .use_delimiter(true)
+    .help("Gitea token, prefixed by its host (e.g. gitea.example.com=TOKEN)");
+
+  let bitbucket_app_password_arg = Arg::with_name("bitbucket-app-password")
+    .long("bitbucket-app-password")
+    .env("BITBUCKET_APP_PASSWORD")
+    .multiple(true)
+    .number_of_values(1)
+    .use_delimiter(true)
+    .help("Bitbucket credentials as username:app_password, prefixed by its host for Server and Data Center instances (e.g. bitbucket.example.com=username:app_password)");
+
+  let advisory_db_arg = Arg::with_name("advisory-db")
+    .long("advisory-db")
+    .env("RUSTSEC_ADVISORY_DB")
+    .takes_value(true)
+    .help(
+      "Path of a local checkout of the RustSec advisory database, to audit the locked packages",
+    );
+
+  let audit_index_arg = Arg::with_name("index")
+    .long("index")
+    .takes_value(true)
+    .help("URL of a sparse index (sparse+https://...), or path of a local checkout of a git index, to check yanked versions");
+
+  let ref_arg = Arg::with_name("ref")
+    .long("ref")
+    .takes_value(true)
+    .help("Branch, tag or commit to fetch (default branch by default)");
+
+  let github_host_arg = Arg::with_name("github-host")
+    .long("github-host")
+    .takes_value(true)
+    .help("Host of a GitHub Enterprise Server instance");
+
+  let gitlab_host_arg = Arg::with_name("gitlab-host")
+    .long("gitlab-host")
+    .takes_value(true)
+    .help("Host of a self-hosted GitLab instance");
+
+  let gitea_host_arg = Arg::with_name("gitea-host")
+    .long("gitea-host")
+    .takes_value(true)
+    .help("Host of a Gitea (or Forgejo) instance");
+
+  let bitbucket_host_arg = Arg::with_name("bitbucket-host")
+    .long("bitbucket-host")
+    .takes_value(true)
+    .help("Host of a Bitbucket Server or Data Center instance");
 
   let matches = App::new(PROJECT_NAME)
     .subcommand(
@@ -41,6 +111,26 @@ fn main() {
             .default_value(OPEN_SOURCE_WORKERS_URL)
             .takes_value(true)
             .multiple(true),
+        )
+        .arg(
+          github_host_arg
+            .clone()
+            .help("Default host of GitHub repositories without host in the description"),
+        )
+        .arg(
+          gitlab_host_arg
+            .clone()
+            .help("Default host of GitLab repositories without host in the description"),
+        )
+        .arg(
+          gitea_host_arg
+            .clone()
+            .help("Default host of Gitea repositories without host in the description"),
+        )
+        .arg(
+          bitbucket_host_arg
+            .clone()
+            .help("Default host of Bitbucket repositories without host in the description"),
         ),
     )
     .subcommand(
@@ -59,9 +149,24 @@ fn main() {
             .short("p")
             .long("provider")
             .takes_value(true)
-            .possible_values(&["github", "gitlab"])
+            .possible_values(PROVIDERS)
             .required(true),
         )
+        .arg(github_host_arg.clone())
+        .arg(gitlab_host_arg.clone())
+        .arg(gitea_host_arg.clone())
+        .arg(bitbucket_host_arg.clone())
+        .arg(
+          Arg::with_name("api-url")
+            .long("api-url")
+            .takes_value(true)
+            .help("Base URL of the provider API, when it is not deduced from the host"),
+        )
+        .arg(ref_arg.clone())
+        .arg(github_token_arg.clone())
+        .arg(gitlab_token_arg.clone())
+        .arg(gitea_token_arg.clone())
+        .arg(bitbucket_app_password_arg.clone())
         .arg(
           Arg::with_name("manifest")
             .short("m")
@@ -93,9 +198,14 @@ fn main() {
             .short("p")
             .long("provider")
             .takes_value(true)
-            .possible_values(&["github", "gitlab"])
+            .possible_values(PROVIDERS)
             .required(true),
-        ),
+        )
+        .arg(github_host_arg)
+        .arg(gitlab_host_arg)
+        .arg(gitea_host_arg)
+        .arg(bitbucket_host_arg)
+        .arg(ref_arg),
     )
     .subcommand(
       SubCommand::with_name("show")
@@ -107,7 +217,22 @@ fn main() {
         .about("Update local cache from repositories")
         .version("0.1")
         .arg(github_token_arg.clone())
-        .arg(gitlab_token_arg.clone()),
+        .arg(gitlab_token_arg.clone())
+        .arg(gitea_token_arg.clone())
+        .arg(bitbucket_app_password_arg.clone())
+        .arg(
+          Arg::with_name("jobs")
+            .short("j")
+            .long("jobs")
+            .takes_value(true)
+            .default_value("4")
+            .help("Number of concurrent requests"),
+        )
+        .arg(
+          Arg::with_name("batch")
+            .long("batch")
+            .help("Request files of many GitHub repositories together through the GraphQL API"),
+        ),
     )
     .subcommand(
       SubCommand::with_name("list")
@@ -115,18 +240,126 @@ fn main() {
         .version("0.1")
         .arg(github_token_arg)
         .arg(gitlab_token_arg)
+        .arg(gitea_token_arg)
+        .arg(bitbucket_app_password_arg)
         .arg(
           Arg::with_name("dependencies")
             .short("d")
             .long("dependencies"),
         )
+        .arg(advisory_db_arg.clone())
+        .arg(audit_index_arg.clone())
         .arg(
           Arg::with_name("exclude-sdk-versions")
             .short("e")
             .long("exclude-sdk-versions")
             .takes_value(true),
+        )
+        .arg(
+          Arg::with_name("format")
+            .short("f")
+            .long("format")
+            .takes_value(true)
+            .possible_values(report::FORMATS)
+            .default_value("text")
+            .help("Output format of the report"),
         ),
     )
+    .subcommand(
+      SubCommand::with_name("audit")
+        .about("Check the locked packages of workers against security advisories")
+        .version("0.1")
+        .arg(advisory_db_arg.required(true))
+        .arg(audit_index_arg)
+        .arg(
+          Arg::with_name("format")
+            .short("f")
+            .long("format")
+            .takes_value(true)
+            .possible_values(report::FORMATS)
+            .default_value("text")
+            .help("Output format of the report"),
+        ),
+    )
+    .subcommand(
+      SubCommand::with_name("outdated")
+        .about("Compare the dependencies of workers with the newest versions of a crates index")
+        .version("0.1")
+        .arg(
+          Arg::with_name("index")
+            .long("index")
+            .env("CRATES_INDEX")
+            .takes_value(true)
+            .default_value(index::DEFAULT_INDEX)
+            .help("URL of a sparse index (sparse+https://...), or path of a local checkout of a git index"),
+        )
+        .arg(
+          Arg::with_name("jobs")
+            .short("j")
+            .long("jobs")
+            .takes_value(true)
+            .default_value("4")
+            .help("Number of concurrent requests"),
+        )
+        .arg(
+          Arg::with_name("format")
+            .short("f")
+            .long("format")
+            .takes_value(true)
+            .possible_values(report::FORMATS)
+            .default_value("text")
+            .help("Output format of the report"),
+        ),
+    )
+    .subcommand(
+      SubCommand::with_name("drift")
+        .about("Compare the requirements of dependencies across workers")
+        .version("0.1")
+        .arg(
+          Arg::with_name("all")
+            .short("a")
+            .long("all")
+            .help("Show all dependencies, not only the ones required with several requirements"),
+        )
+        .arg(
+          Arg::with_name("format")
+            .short("f")
+            .long("format")
+            .takes_value(true)
+            .possible_values(report::FORMATS)
+            .default_value("text")
+            .help("Output format of the report"),
+        ),
+    )
+    .subcommand(
+      SubCommand::with_name("check")
+        .about("Check registered workers against the expected versions")
+        .version("0.1")
+        .arg(
+          Arg::with_name("warn")
+            .long("warn")
+            .takes_value(true)
+            .possible_values(version::THRESHOLDS)
+            .default_value("patch")
+            .help("Lag from which versions behind the expected ones are reported"),
+        )
+        .arg(
+          Arg::with_name("fail")
+            .long("fail")
+            .takes_value(true)
+            .possible_values(version::THRESHOLDS)
+            .default_value("minor")
+            .help("Lag from which versions behind the expected ones fail the check"),
+        ),
+    )
+    .subcommand(
+      SubCommand::with_name("cache")
+        .about("Manage files fetched from repositories")
+        .version("0.1")
+        .setting(AppSettings::SubcommandRequiredElseHelp)
+        .subcommand(SubCommand::with_name("info").about("Display the size of cached files"))
+        .subcommand(SubCommand::with_name("clear").about("Remove all cached files")),
+    )
     .subcommand(
       SubCommand::with_name("versions")
         .about("Set versions of tools (Rust, Media-Cloud-AI SDK)")
@@ -143,7 +376,9 @@ fn main() {
   let mut cfg = config::McaiWorkersConfig::open();
 
   if let Some(matches) = matches.subcommand_matches("register") {
-    actions::register(&mut cfg, matches);
+    if !actions::register(&mut cfg, matches) {
+      std::process::exit(1);
+    }
     return;
   }
 
@@ -163,17 +398,51 @@ fn main() {
   }
 
   if let Some(matches) = matches.subcommand_matches("fetch") {
-    actions::fetch(&mut cfg, matches);
+    if !actions::fetch(&mut cfg, matches) {
+      std::process::exit(1);
+    }
     return;
   }
 
   if let Some(matches) = matches.subcommand_matches("list") {
-    actions::list(&cfg, matches);
+    if !actions::list(&cfg, matches) {
+      std::process::exit(1);
+    }
+    return;
+  }
+
+  if let Some(matches) = matches.subcommand_matches("audit") {
+    if !actions::audit(&cfg, matches) {
+      std::process::exit(1);
+    }
+    return;
+  }
+
+  if let Some(matches) = matches.subcommand_matches("outdated") {
+    if !actions::outdated(&cfg, matches) {
+      std::process::exit(1);
+    }
+    return;
+  }
+
+  if let Some(matches) = matches.subcommand_matches("drift") {
+    actions::drift(&cfg, matches);
+    return;
+  }
+
+  if let Some(matches) = matches.subcommand_matches("check") {
+    if !actions::check(&cfg, matches) {
+      std::process::exit(1);
+    }
+    return;
+  }
+
+  if let Some(matches) = matches.subcommand_matches("cache") {
+    actions::cache(&cfg, matches);
     return;
   }
 
   if let Some(matches) = matches.subcommand_matches("versions") {
     actions::versions(&mut cfg, matches);
-    return;
   }
 }
@@ -0,0 +1,39 @@
+use std::{
+  sync::{
+    atomic::{AtomicUsize, Ordering},
+    Mutex,
+  },
+  thread,
+};
+
+/// Apply a function on every item with at most `jobs` threads, keeping the order of the items
+pub fn map<T, R, F>(items: &[T], jobs: usize, function: F) -> Vec<R>
+where
+  T: Sync,
+  R: Send,
+  F: Fn(&T) -> R + Sync,
+{
+  let next = AtomicUsize::new(0);
+  let results = Mutex::new((0..items.len()).map(|_| None).collect::<Vec<Option<R>>>());
+
+  thread::scope(|scope| {
+    for _ in 0..jobs.clamp(1, items.len().max(1)) {
+      scope.spawn(|| loop {
+        let index = next.fetch_add(1, Ordering::SeqCst);
+        if index >= items.len() {
+          break;
+        }
+
+        let result = function(&items[index]);
+        results.lock().unwrap()[index] = Some(result);
+      });
+    }
+  });
+
+  results
+    .into_inner()
+    .unwrap()
+    .into_iter()
+    .map(|result| result.unwrap())
+    .collect()
+}
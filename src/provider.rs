@@ -0,0 +1,356 @@
+use crate::{
+  bitbucket::{self, BitbucketCloud, BitbucketServer},
+  cache,
+  config::{McaiWorkersConfig, Provider, RepoConfig},
+  error::{Error, Result},
+  git::Git,
+  gitea::{self, Gitea},
+  github::{self, Github},
+  gitlab::{self, Gitlab},
+  http,
+  local::Local,
+};
+use clap::ArgMatches;
+use percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use reqwest::{
+  blocking::{RequestBuilder, Response},
+  header::{ETAG, IF_NONE_MATCH},
+  StatusCode,
+};
+use serde::de::DeserializeOwned;
+
+// characters kept as is in the components of API URLs, others (like `/` of references) are percent-encoded
+const URL_COMPONENT: &AsciiSet = &NON_ALPHANUMERIC.remove(b'-').remove(b'.').remove(b'_');
+
+/// File of a repository tree
+#[derive(Clone, Debug)]
+pub struct TreeFile {
+  pub path: String,
+  // blob SHA of the file, when provided by the provider
+  pub sha: Option<String>,
+}
+
+/// Content of a fetched file
+#[derive(Clone, Debug)]
+pub struct FileContent {
+  pub content: String,
+  // blob SHA of the content, when provided by the provider
+  pub sha: Option<String>,
+  // HTTP entity tag of the content, to send conditional requests on next fetches
+  pub etag: Option<String>,
+}
+
+/// File of a repository requested in a batch
+#[derive(Clone, Debug)]
+pub struct FileRequest<'a> {
+  pub repository: &'a str,
+  pub reference: &'a str,
+  pub path: &'a str,
+}
+
+/// Repository resolved in a batch, at its reference or, without reference, at its default branch
+#[derive(Clone, Debug)]
+pub struct ResolveRequest<'a> {
+  pub repository: &'a str,
+  pub reference: Option<&'a str>,
+}
+
+/// Commit of a resolved reference
+#[derive(Clone, Debug)]
+pub struct Resolved {
+  pub reference: String,
+  pub commit: String,
+  // files of the repository at the commit, when the provider can list them
+  pub tree: Option<Vec<TreeFile>>,
+}
+
+#[derive(Clone, Debug)]
+pub enum FileResponse {
+  Content(FileContent),
+  // the content matches the entity tag sent in the request
+  NotModified,
+}
+
+/// Common interface of the forges hosting worker repositories
+pub trait SourceProvider: Send + Sync {
+  /// Get a file at the given reference (branch, tag or commit), `None` when the file does not exist.
+  /// When an entity tag is given, the provider may answer that the file is not modified.
+  fn get_file(
+    &self,
+    repository: &str,
+    reference: &str,
+    filename: &str,
+    etag: Option<&str>,
+  ) -> Result<Option<FileResponse>>;
+
+  /// List all files of the repository at the given reference
+  fn list_tree(&self, repository: &str, reference: &str) -> Result<Vec<TreeFile>>;
+
+  /// Resolve the default branch of the repository
+  fn default_branch(&self, repository: &str) -> Result<String>;
+
+  /// Resolve a reference (branch, tag or commit) to a commit SHA
+  fn commit_sha(&self, repository: &str, reference: &str) -> Result<String>;
+
+  /// Get a key shared by the providers able to resolve repositories and get their files in the same batched requests,
+  /// `None` when repositories and files are requested one by one
+  fn batch_key(&self) -> Option<String> {
+    None
+  }
+
+  /// Resolve the reference (the default branch without reference) of a repository to its commit and tree
+  fn resolve(&self, repository: &str, reference: Option<&str>) -> Result<Resolved> {
+    let reference = match reference {
+      Some(reference) => reference.to_string(),
+      None => self.default_branch(repository)?,
+    };
+    let commit = self.commit_sha(repository, &reference)?;
+
+    // configured files are requested anyway when the tree can not be listed
+    let tree = self.list_tree(repository, &commit).ok();
+
+    Ok(Resolved {
+      reference,
+      commit,
+      tree,
+    })
+  }
+
+  /// Resolve several repositories, as `resolve` does for each of them.
+  /// The outer error fails the whole batch, the inner ones a single repository.
+  fn resolve_all(&self, requests: &[ResolveRequest]) -> Result<Vec<Result<Resolved>>> {
+    Ok(
+      requests
+        .iter()
+        .map(|request| self.resolve(request.repository, request.reference))
+        .collect(),
+    )
+  }
+
+  /// Get files of several repositories, as `get_file` does for each of them.
+  /// The outer error fails the whole batch, the inner ones a single file.
+  fn get_files(&self, requests: &[FileRequest]) -> Result<Vec<Result<Option<FileResponse>>>> {
+    Ok(
+      requests
+        .iter()
+        .map(|request| self.get_file(request.repository, request.reference, request.path, None))
+        .collect(),
+    )
+  }
+}
+
+/// Build the provider client of a repository, failing when the required token is not available.
+/// Local and git repositories do not require any token.
+pub fn new<'a>(
+  cfg: &McaiWorkersConfig,
+  repo: &RepoConfig,
+  matches: &ArgMatches<'a>,
+) -> Result<Box<dyn SourceProvider>> {
+  let hosts = |default_host| hosts(cfg, repo, default_host);
+
+  match repo.provider {
+    Provider::Github => {
+      let host = repo.host.as_deref().unwrap_or(github::DEFAULT_HOST);
+      let api_url = repo
+        .api_url
+        .clone()
+        .unwrap_or_else(|| github::api_url(host));
+
+      let token = select_token(matches, "github-token", host, &hosts(github::DEFAULT_HOST))?;
+      Ok(Box::new(Github::new(&api_url, token)?))
+    }
+    Provider::Gitlab => {
+      let host = repo.host.as_deref().unwrap_or(gitlab::DEFAULT_HOST);
+      let api_url = repo
+        .api_url
+        .clone()
+        .unwrap_or_else(|| gitlab::api_url(host));
+
+      let token = select_token(matches, "gitlab-token", host, &hosts(gitlab::DEFAULT_HOST))?;
+      Ok(Box::new(Gitlab::new(&api_url, token)?))
+    }
+    Provider::Gitea => {
+      let host = repo.host.as_deref().unwrap_or(gitea::DEFAULT_HOST);
+      let api_url = repo.api_url.clone().unwrap_or_else(|| gitea::api_url(host));
+
+      let token = select_token(matches, "gitea-token", host, &hosts(gitea::DEFAULT_HOST))?;
+      Ok(Box::new(Gitea::new(&api_url, token)?))
+    }
+    Provider::Bitbucket => {
+      let host = repo.host.as_deref().unwrap_or(bitbucket::CLOUD_HOST);
+      let api_url = repo
+        .api_url
+        .clone()
+        .unwrap_or_else(|| bitbucket::api_url(host));
+
+      let credentials = select_token(
+        matches,
+        "bitbucket-app-password",
+        host,
+        &hosts(bitbucket::CLOUD_HOST),
+      )?;
+      if host == bitbucket::CLOUD_HOST {
+        Ok(Box::new(BitbucketCloud::new(&api_url, credentials)?))
+      } else {
+        Ok(Box::new(BitbucketServer::new(&api_url, credentials)?))
+      }
+    }
+    Provider::Local => Ok(Box::new(Local::new(&repo.name))),
+    Provider::Git => Ok(Box::new(Git::new(&cache::git_directory(), &repo.name)?)),
+  }
+}
+
+/// Get the hosts of the provider of a repository, configured by the registered repositories or by default
+fn hosts<'c>(
+  cfg: &'c McaiWorkersConfig,
+  repo: &'c RepoConfig,
+  default_host: &'c str,
+) -> Vec<&'c str> {
+  cfg
+    .repos
+    .iter()
+    .chain(std::iter::once(repo))
+    .filter(|other| other.provider == repo.provider)
+    .map(|other| other.host.as_deref().unwrap_or(default_host))
+    .chain(std::iter::once(default_host))
+    .collect()
+}
+
+/// Select the token of a host, among values formatted as `[host=]token`.
+/// The prefix is only a host when it is one of the configured `hosts`, tokens may contain `=` too.
+/// A token without host is used for any host without a dedicated token.
+fn select_token<'a>(
+  matches: &'a ArgMatches,
+  name: &str,
+  host: &str,
+  hosts: &[&str],
+) -> Result<&'a str> {
+  let missing_token = || Error::MissingToken(name.to_string(), host.to_string());
+
+  let tokens: Vec<(Option<&str>, &str)> = matches
+    .values_of(name)
+    .ok_or_else(missing_token)?
+    .map(|value| match value.split_once('=') {
+      Some((token_host, token)) if hosts.contains(&token_host) => (Some(token_host), token),
+      _ => (None, value),
+    })
+    .collect();
+
+  tokens
+    .iter()
+    .find(|(token_host, _)| *token_host == Some(host))
+    .or_else(|| tokens.iter().find(|(token_host, _)| token_host.is_none()))
+    .map(|(_, token)| *token)
+    .ok_or_else(missing_token)
+}
+
+/// Send a file request, conditional when an entity tag is given.
+/// The content and blob SHA are read from a successful response with the `read` function.
+pub fn request_file<F>(
+  request: RequestBuilder,
+  etag: Option<&str>,
+  read: F,
+) -> Result<Option<FileResponse>>
+where
+  F: FnOnce(Response) -> Result<(String, Option<String>)>,
+{
+  let request = match etag {
+    Some(etag) => request.header(IF_NONE_MATCH, etag),
+    None => request,
+  };
+
+  let response = http::send(request)?;
+  match response.status() {
+    StatusCode::NOT_MODIFIED => Ok(Some(FileResponse::NotModified)),
+    StatusCode::NOT_FOUND => Ok(None),
+    StatusCode::OK => {
+      let etag = response
+        .headers()
+        .get(ETAG)
+        .and_then(|etag| etag.to_str().ok())
+        .map(|etag| etag.to_string());
+
+      let (content, sha) = read(response)?;
+      Ok(Some(FileResponse::Content(FileContent {
+        content,
+        sha,
+        etag,
+      })))
+    }
+    status => Err(Error::Status(response.url().to_string(), status)),
+  }
+}
+
+/// Get the JSON body of a successful response
+pub fn json<T: DeserializeOwned>(response: Response) -> Result<T> {
+  match response.status() {
+    StatusCode::OK => Ok(response.json()?),
+    status => Err(Error::Status(response.url().to_string(), status)),
+  }
+}
+
+/// Split the name of a repository into its owner (organization, workspace or project) and its name
+pub fn owner_and_name(repository: &str) -> Result<(&str, &str)> {
+  match repository.split_once('/') {
+    Some((owner, name)) if !owner.is_empty() && !name.is_empty() && !name.contains('/') => {
+      Ok((owner, name))
+    }
+    _ => Err(Error::Api(format!(
+      "invalid repository {}, expected owner/name",
+      repository
+    ))),
+  }
+}
+
+/// Percent-encode a path segment or a query value of an API URL, `/` included
+pub fn encode(component: &str) -> String {
+  utf8_percent_encode(component, URL_COMPONENT).to_string()
+}
+
+/// Percent-encode the segments of a file path of an API URL, keeping the `/` separating them
+pub fn encode_path(path: &str) -> String {
+  path
+    .split('/')
+    .map(encode)
+    .collect::<Vec<String>>()
+    .join("/")
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn repository_owners_and_names() {
+    let repositories = vec![
+      (
+        "media-cloud-ai/mcai_workers",
+        Some(("media-cloud-ai", "mcai_workers")),
+      ),
+      ("PROJECT/repository", Some(("PROJECT", "repository"))),
+      ("repository", None),
+      ("owner/", None),
+      ("/repository", None),
+      ("group/subgroup/repository", None),
+    ];
+
+    for (repository, expected) in repositories {
+      assert_eq!(
+        owner_and_name(repository).ok(),
+        expected,
+        "owner and name of {}",
+        repository
+      );
+    }
+  }
+
+  #[test]
+  fn encoded_components() {
+    assert_eq!(encode("feature/v1.2_rc-1"), "feature%2Fv1.2_rc-1");
+    assert_eq!(encode("a b#c?d"), "a%20b%23c%3Fd");
+    assert_eq!(
+      encode_path("docker/worker #1/Dockerfile"),
+      "docker/worker%20%231/Dockerfile"
+    );
+  }
+}
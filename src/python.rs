@@ -0,0 +1,338 @@
+use crate::{
+  cargo::{self, Lockfile},
+  config::{FileKind, RepoConfig},
+  error::{Error, Result},
+};
+use semver::{Version, VersionReq};
+use toml::Value;
+
+// names of the Python package of the SDK, normalized as in PEP 503
+static SDK_PACKAGES: &[&str] = &["mcai-worker-sdk", "py-mcai-worker-sdk"];
+const POETRY_LOCK_FILENAME: &str = "poetry.lock";
+
+/// Requirement of the MCAI worker SDK declared by a Python file
+pub struct SdkRequirement {
+  pub path: String,
+  // specifiers as written in the file, like `>=1.2,<2`
+  pub specifiers: String,
+  // specifiers converted to a semver requirement, when possible
+  pub requirement: Option<VersionReq>,
+}
+
+/// Find the requirements of the MCAI worker SDK in the fetched Python files of a repository
+pub fn sdk_requirements(repo: &RepoConfig) -> Vec<SdkRequirement> {
+  repo
+    .fetched_files(FileKind::Python)
+    .into_iter()
+    .filter_map(|(path, content)| {
+      let specifiers = match filename(&path) {
+        "pyproject.toml" => pyproject_specifiers(content),
+        "setup.py" => setup_specifiers(content),
+        POETRY_LOCK_FILENAME => None,
+        _ => requirements_specifiers(content),
+      }?;
+
+      let requirement = semver_requirement(&specifiers);
+      Some(SdkRequirement {
+        path,
+        specifiers,
+        requirement,
+      })
+    })
+    .collect()
+}
+
+/// Get the SDK version locked by the `poetry.lock` of a Python file, with whether it satisfies the requirement
+pub fn locked_sdk_version(
+  repo: &RepoConfig,
+  requirement: &SdkRequirement,
+) -> Option<Result<(Version, bool)>> {
+  let directory = cargo::parent_directory(&requirement.path);
+  let path = cargo::join(directory, POETRY_LOCK_FILENAME);
+
+  let (_, content) = repo
+    .fetched_files(FileKind::Python)
+    .into_iter()
+    .find(|(other, _)| *other == path)?;
+
+  let lockfile: Lockfile = match toml::from_str(content) {
+    Ok(lockfile) => lockfile,
+    Err(error) => return Some(Err(Error::Manifest(error.to_string()))),
+  };
+
+  lockfile
+    .locked_version(is_sdk_package, requirement.requirement.as_ref())
+    .map(Ok)
+}
+
+fn filename(path: &str) -> &str {
+  path.rsplit('/').next().unwrap_or(path)
+}
+
+/// Normalize a package name as in PEP 503
+fn normalize(name: &str) -> String {
+  name
+    .split(['-', '_', '.'])
+    .filter(|part| !part.is_empty())
+    .collect::<Vec<&str>>()
+    .join("-")
+    .to_lowercase()
+}
+
+fn is_sdk_package(name: &str) -> bool {
+  SDK_PACKAGES.contains(&normalize(name).as_str())
+}
+
+/// Parse a PEP 508 requirement, like `name[extra] >=1.0 ; python_version > "3.6"`, into its name and specifiers
+fn parse_requirement(requirement: &str) -> Option<(&str, &str)> {
+  let requirement = requirement.split(';').next()?.trim();
+
+  let end = requirement
+    .find(|c: char| !(c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.'))
+    .unwrap_or(requirement.len());
+  let (name, specifiers) = requirement.split_at(end);
+  if name.is_empty() {
+    return None;
+  }
+
+  let specifiers = specifiers.trim_start();
+  let specifiers = match specifiers.strip_prefix('[') {
+    Some(extras) => extras
+      .split_once(']')
+      .map_or("", |(_, specifiers)| specifiers),
+    None => specifiers,
+  };
+  let specifiers = specifiers
+    .trim()
+    .trim_start_matches('(')
+    .trim_end_matches(')')
+    .trim();
+
+  // direct references (`name @ url`) do not pin any version
+  if specifiers.starts_with('@') {
+    return Some((name, ""));
+  }
+
+  Some((name, specifiers))
+}
+
+fn sdk_specifiers<'a>(requirements: impl Iterator<Item = &'a str>) -> Option<String> {
+  requirements
+    .filter_map(parse_requirement)
+    .find(|(name, _)| is_sdk_package(name))
+    .map(|(_, specifiers)| specifiers.to_string())
+}
+
+fn requirements_specifiers(content: &str) -> Option<String> {
+  sdk_specifiers(
+    content
+      .lines()
+      .map(|line| line.split(" #").next().unwrap_or_default().trim())
+      .filter(|line| !line.is_empty() && !line.starts_with('#') && !line.starts_with('-')),
+  )
+}
+
+fn pyproject_specifiers(content: &str) -> Option<String> {
+  let pyproject: Value = toml::from_str(content).ok()?;
+
+  // PEP 621 dependencies
+  let dependencies = pyproject
+    .get("project")
+    .and_then(|project| project.get("dependencies"))
+    .and_then(|dependencies| dependencies.as_array());
+  if let Some(dependencies) = dependencies {
+    let specifiers = sdk_specifiers(dependencies.iter().filter_map(|value| value.as_str()));
+    if specifiers.is_some() {
+      return specifiers;
+    }
+  }
+
+  // Poetry dependencies, constraints being a string or a table with a version
+  let dependencies = pyproject
+    .get("tool")
+    .and_then(|tool| tool.get("poetry"))
+    .and_then(|poetry| poetry.get("dependencies"))
+    .and_then(|dependencies| dependencies.as_table())?;
+
+  dependencies
+    .iter()
+    .find(|(name, _)| is_sdk_package(name))
+    .map(|(_, constraint)| match constraint {
+      Value::String(version) => version.clone(),
+      constraint => constraint
+        .get("version")
+        .and_then(|version| version.as_str())
+        .unwrap_or_default()
+        .to_string(),
+    })
+}
+
+fn setup_specifiers(content: &str) -> Option<String> {
+  // requirements are the string literals starting with the name of the package
+  let mut literals = vec![];
+  let mut chars = content.char_indices();
+  while let Some((start, c)) = chars.next() {
+    if c == '"' || c == '\'' {
+      if let Some((end, _)) = chars.find(|(_, other)| *other == c) {
+        literals.push(&content[start + 1..end]);
+      }
+    }
+  }
+
+  sdk_specifiers(literals.into_iter())
+}
+
+/// Convert PEP 440 (or Poetry) version specifiers to a semver requirement.
+/// Exclusions (`!=`) can not be expressed and are ignored.
+pub fn semver_requirement(specifiers: &str) -> Option<VersionReq> {
+  let mut predicates = vec![];
+
+  for specifier in specifiers.split(',').map(|specifier| specifier.trim()) {
+    if specifier.is_empty() || specifier == "*" {
+      continue;
+    }
+
+    let operator_length = specifier
+      .find(|c: char| c.is_ascii_digit())
+      .unwrap_or(specifier.len());
+    let (operator, version) = specifier.split_at(operator_length);
+    let version = release(version);
+    if version.is_empty() {
+      return None;
+    }
+
+    match operator.trim() {
+      "~=" => {
+        let mut components: Vec<u64> = version
+          .split('.')
+          .map(|component| component.parse().ok())
+          .collect::<Option<Vec<u64>>>()?;
+        if components.len() < 2 {
+          return None;
+        }
+        components.pop();
+        *components.last_mut()? += 1;
+
+        let upper: Vec<String> = components
+          .iter()
+          .map(|component| component.to_string())
+          .collect();
+        predicates.push(format!(">={}", version));
+        predicates.push(format!("<{}", upper.join(".")));
+      }
+      "==" | "===" if version.ends_with('*') => predicates.push(version.to_string()),
+      // Poetry constraints without operator are exact, missing components being zero
+      "==" | "===" | "" => predicates.push(format!("={}", padded(version))),
+      "!=" => {}
+      operator @ (">=" | "<=" | ">" | "<" | "^" | "~") => {
+        predicates.push(format!("{}{}", operator, version))
+      }
+      _ => return None,
+    }
+  }
+
+  if predicates.is_empty() {
+    VersionReq::parse("*").ok()
+  } else {
+    VersionReq::parse(&predicates.join(", ")).ok()
+  }
+}
+
+/// Pad a release to three components, like `1.2.0` for `1.2`, as semver matches partial versions as ranges
+fn padded(version: &str) -> String {
+  let missing = 3usize.saturating_sub(version.split('.').count());
+  format!("{}{}", version, ".0".repeat(missing))
+}
+
+/// Get the release segment of a PEP 440 version, like `1.2` in `1.2rc1` or `1.2.post3`
+fn release(version: &str) -> &str {
+  let end = version
+    .find(|c: char| !(c.is_ascii_digit() || c == '.' || c == '*'))
+    .unwrap_or(version.len());
+  version[..end].trim_end_matches('.')
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use semver::Version;
+
+  #[test]
+  fn parse_requirements() {
+    let cases = vec![
+      ("mcai_worker_sdk>=1.0", Some(("mcai_worker_sdk", ">=1.0"))),
+      (
+        "mcai-worker-sdk == 1.2.*",
+        Some(("mcai-worker-sdk", "== 1.2.*")),
+      ),
+      (
+        "mcai_worker_sdk (>=1.0, <2)",
+        Some(("mcai_worker_sdk", ">=1.0, <2")),
+      ),
+      (
+        "mcai_worker_sdk[media] >=1.0, <2 ; python_version > \"3.6\"",
+        Some(("mcai_worker_sdk", ">=1.0, <2")),
+      ),
+      (
+        "mcai_worker_sdk[media]; extra == \"media\"",
+        Some(("mcai_worker_sdk", "")),
+      ),
+      (
+        "mcai_worker_sdk @ https://example.com/mcai_worker_sdk-1.0.whl",
+        Some(("mcai_worker_sdk", "")),
+      ),
+      ("mcai_worker_sdk", Some(("mcai_worker_sdk", ""))),
+      (">=1.0", None),
+      ("", None),
+    ];
+
+    for (requirement, expected) in cases {
+      assert_eq!(parse_requirement(requirement), expected, "{}", requirement);
+    }
+  }
+
+  #[test]
+  fn convert_specifiers() {
+    let cases = vec![
+      ("~=1.2", "1.2.0", true),
+      ("~=1.2", "1.9.3", true),
+      ("~=1.2", "1.1.9", false),
+      ("~=1.2", "2.0.0", false),
+      ("~=1.2.3", "1.2.5", true),
+      ("~=1.2.3", "1.3.0", false),
+      ("==1.*", "1.5.2", true),
+      ("==1.*", "2.0.0", false),
+      ("==1.2", "1.2.0", true),
+      ("==1.2", "1.2.1", false),
+      ("==1", "1.0.0", true),
+      ("==1", "1.3.0", false),
+      ("==1.2rc1", "1.2.0", true),
+      (">=1.0, <2", "1.9.0", true),
+      (">=1.0, <2", "2.0.0", false),
+      (">=1.0, !=1.5", "1.5.0", true),
+      ("1.2.3", "1.2.3", true),
+      ("^1.2", "1.9.0", true),
+      ("^1.2", "2.0.0", false),
+      ("", "0.1.0", true),
+      ("*", "3.0.0", true),
+    ];
+
+    for (specifiers, version, matches) in cases {
+      let requirement = semver_requirement(specifiers).unwrap();
+      assert_eq!(
+        requirement.matches(&Version::parse(version).unwrap()),
+        matches,
+        "{} against {}",
+        specifiers,
+        version
+      );
+    }
+  }
+
+  #[test]
+  fn reject_invalid_specifiers() {
+    for specifiers in &["~=1", "=>1.0", ">=", "==latest"] {
+      assert!(semver_requirement(specifiers).is_none(), "{}", specifiers);
+    }
+  }
+}
@@ -0,0 +1,182 @@
+use super::{csv, markdown, text, FileError, Format};
+use crate::{
+  audit::{Auditor, Finding},
+  config::McaiWorkersConfig,
+};
+use colored::Colorize;
+use console::Emoji;
+use std::fmt::Write;
+
+/// Advisories and yanked versions of the packages locked by all workers
+#[derive(Debug, Serialize)]
+pub struct AuditReport {
+  pub workers: Vec<WorkerAudit>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct WorkerAudit {
+  pub name: String,
+  pub findings: Vec<Finding>,
+  // lockfiles which can not be audited
+  pub errors: Vec<FileError>,
+}
+
+impl AuditReport {
+  /// Audit the fetched `Cargo.lock` files of all workers
+  pub fn new(cfg: &McaiWorkersConfig, auditor: &Auditor) -> AuditReport {
+    let workers = cfg
+      .repos
+      .iter()
+      .map(|repo| {
+        let (findings, errors) = auditor.audit(repo);
+        WorkerAudit {
+          name: repo.name.clone(),
+          findings,
+          errors: errors
+            .into_iter()
+            .map(|(path, error)| FileError { path, error })
+            .collect(),
+        }
+      })
+      .collect();
+
+    AuditReport { workers }
+  }
+
+  /// Whether a worker locks a package with an advisory or a yanked version
+  pub fn has_findings(&self) -> bool {
+    self
+      .workers
+      .iter()
+      .any(|worker| !worker.findings.is_empty())
+  }
+
+  pub fn render(&self, format: Format) -> String {
+    match format {
+      Format::Text => self.render_text(),
+      Format::Json => format!("{}\n", serde_json::to_string_pretty(self).unwrap()),
+      Format::Yaml => serde_yaml::to_string(self).unwrap(),
+      Format::Csv => self.render_csv(),
+      Format::Markdown => self.render_markdown(),
+    }
+  }
+
+  fn render_text(&self) -> String {
+    let mut output = String::new();
+
+    for worker in &self.workers {
+      if worker.findings.is_empty() && worker.errors.is_empty() {
+        continue;
+      }
+
+      writeln!(output).unwrap();
+      writeln!(
+        output,
+        "{} {}",
+        Emoji("🚀", &"=>".green().bold()),
+        worker.name.green().bold()
+      )
+      .unwrap();
+
+      for error in &worker.errors {
+        writeln!(
+          output,
+          "  {} {}: {}",
+          Emoji("❗", "=>"),
+          error.path,
+          error.error.red()
+        )
+        .unwrap();
+      }
+      for finding in &worker.findings {
+        text::render_finding(&mut output, finding).unwrap();
+      }
+    }
+
+    let findings: usize = self
+      .workers
+      .iter()
+      .map(|worker| worker.findings.len())
+      .sum();
+    let workers = self
+      .workers
+      .iter()
+      .filter(|worker| !worker.findings.is_empty())
+      .count();
+    writeln!(
+      output,
+      "\n{} advisory(ies) in {} of {} worker(s)",
+      findings,
+      workers,
+      self.workers.len()
+    )
+    .unwrap();
+
+    output
+  }
+
+  fn render_csv(&self) -> String {
+    let mut output = csv::line(
+      [
+        "repository",
+        "path",
+        "package",
+        "version",
+        "kind",
+        "advisory",
+        "title",
+        "url",
+      ]
+      .iter()
+      .map(|column| column.to_string()),
+    );
+
+    for worker in &self.workers {
+      for finding in &worker.findings {
+        output.push_str(&csv::line(
+          vec![
+            worker.name.clone(),
+            finding.path.clone(),
+            finding.package.clone(),
+            finding.version.clone(),
+            finding.kind.clone(),
+            finding.advisory.clone().unwrap_or_default(),
+            finding.title.clone(),
+            finding.url.clone().unwrap_or_default(),
+          ]
+          .into_iter(),
+        ));
+      }
+    }
+
+    output
+  }
+
+  fn render_markdown(&self) -> String {
+    let mut output = String::from("# Security audit\n\n");
+    output.push_str("| Repository | Lockfile | Package | Advisory | Kind |\n");
+    output.push_str("|---|---|---|---|---|\n");
+
+    for worker in &self.workers {
+      for finding in &worker.findings {
+        let advisory = match (&finding.advisory, &finding.url) {
+          (Some(advisory), Some(url)) => format!("[{}]({}) {}", advisory, url, finding.title),
+          (Some(advisory), None) => format!("{} {}", advisory, finding.title),
+          (None, _) => finding.title.clone(),
+        };
+
+        output.push_str(&format!(
+          "| {} | {} | {} {} | {} | {} |\n",
+          markdown::escape(&worker.name),
+          markdown::escape(&finding.path),
+          finding.package,
+          finding.version,
+          markdown::escape(&advisory),
+          finding.kind
+        ));
+      }
+    }
+
+    output
+  }
+}
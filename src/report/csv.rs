@@ -0,0 +1,177 @@
+use super::{Report, SdkRequirement};
+use crate::audit::Finding;
+
+static COLUMNS: &[&str] = &[
+  "repository",
+  "provider",
+  "reference",
+  "commit",
+  "kind",
+  "path",
+  "name",
+  "version",
+  "mcai_sdk_requirement",
+  "mcai_sdk_locked",
+  "rust_version",
+  "update_required",
+  "error",
+];
+
+/// Row of the CSV report, one per repository and per analyzed file
+#[derive(Default)]
+struct Row<'a> {
+  kind: &'a str,
+  path: &'a str,
+  name: String,
+  version: &'a str,
+  sdk: Option<&'a SdkRequirement>,
+  rust_version: &'a str,
+  update_required: bool,
+  error: &'a str,
+}
+
+/// Render a report as CSV, with a row per repository and per analyzed file of each repository
+pub fn render(report: &Report) -> String {
+  let mut output = line(COLUMNS.iter().map(|column| column.to_string()));
+
+  for worker in &report.workers {
+    let mut rows = vec![Row {
+      kind: "repository",
+      update_required: worker.update_required(),
+      error: worker.last_error.as_deref().unwrap_or_default(),
+      ..Default::default()
+    }];
+
+    rows.extend(worker.errors.iter().map(|error| Row {
+      kind: "error",
+      path: &error.path,
+      error: &error.error,
+      ..Default::default()
+    }));
+
+    rows.extend(worker.rust_projects.iter().map(|project| {
+      let rust_version = project.rust_version.as_ref();
+      let sdk = project.mcai_sdk.as_ref();
+
+      Row {
+        kind: if project.name.is_some() {
+          "rust-project"
+        } else {
+          "rust-workspace"
+        },
+        path: &project.path,
+        name: project.name.clone().unwrap_or_default(),
+        version: project.version.as_deref().unwrap_or_default(),
+        sdk,
+        rust_version: rust_version
+          .map(|rust_version| rust_version.version.as_str())
+          .unwrap_or_default(),
+        update_required: sdk.is_some_and(|sdk| sdk.update_required)
+          || rust_version.is_some_and(|rust_version| rust_version.update_required),
+        error: sdk
+          .and_then(|sdk| sdk.lockfile_error.as_deref())
+          .unwrap_or_default(),
+      }
+    }));
+
+    rows.extend(worker.python_projects.iter().map(|project| {
+      Row {
+        kind: "python-project",
+        path: &project.path,
+        sdk: Some(&project.mcai_sdk),
+        update_required: project.mcai_sdk.update_required,
+        error: project
+          .mcai_sdk
+          .lockfile_error
+          .as_deref()
+          .unwrap_or_default(),
+        ..Default::default()
+      }
+    }));
+
+    rows.extend(worker.toolchains.iter().map(|toolchain| Row {
+      kind: "toolchain",
+      path: &toolchain.path,
+      rust_version: &toolchain.channel.version,
+      update_required: toolchain.channel.update_required,
+      ..Default::default()
+    }));
+
+    rows.extend(worker.dockerfiles.iter().map(|dockerfile| {
+      let images: Vec<String> = dockerfile
+        .images
+        .iter()
+        .map(|image| format!("{}:{}", image.image, image.tag))
+        .collect();
+
+      Row {
+        kind: "dockerfile",
+        path: &dockerfile.path,
+        name: images.join(" "),
+        update_required: dockerfile.images.iter().any(|image| image.update_required),
+        error: dockerfile.error.as_deref().unwrap_or_default(),
+        ..Default::default()
+      }
+    }));
+
+    let descriptions: Vec<String> = worker.findings.iter().map(Finding::description).collect();
+    rows.extend(
+      worker
+        .findings
+        .iter()
+        .zip(&descriptions)
+        .map(|(finding, description)| Row {
+          kind: "advisory",
+          path: &finding.path,
+          name: finding.package.clone(),
+          version: &finding.version,
+          error: description,
+          ..Default::default()
+        }),
+    );
+
+    for row in rows {
+      output.push_str(&line(
+        [
+          worker.name.as_str(),
+          &worker.provider,
+          worker.reference.as_deref().unwrap_or_default(),
+          worker.commit.as_deref().unwrap_or_default(),
+          row.kind,
+          row.path,
+          &row.name,
+          row.version,
+          row
+            .sdk
+            .map(|sdk| sdk.requirement.as_str())
+            .unwrap_or_default(),
+          row
+            .sdk
+            .and_then(|sdk| sdk.locked.as_deref())
+            .unwrap_or_default(),
+          row.rust_version,
+          if row.update_required { "true" } else { "false" },
+          row.error,
+        ]
+        .iter()
+        .map(|field| field.to_string()),
+      ));
+    }
+  }
+
+  output
+}
+
+pub fn line(fields: impl Iterator<Item = String>) -> String {
+  let fields: Vec<String> = fields.map(|field| escape(&field)).collect();
+  format!("{}\n", fields.join(","))
+}
+
+/// Quote a field containing separators, quotes or line breaks
+fn escape(field: &str) -> String {
+  if field.contains(&[',', '"', '\n', '\r'][..]) {
+    format!("\"{}\"", field.replace('"', "\"\""))
+  } else {
+    field.to_string()
+  }
+}
@@ -0,0 +1,408 @@
+use super::{csv, markdown, Format};
+use crate::{cargo, config::McaiWorkersConfig};
+use cargo_toml::Dependency as ManifestDependency;
+use colored::Colorize;
+use console::Emoji;
+use semver::{Version, VersionReq};
+use std::{collections::BTreeMap, fmt::Write};
+
+/// Requirements of the dependencies of the Rust projects, across all workers
+#[derive(Debug, Serialize)]
+pub struct DriftReport {
+  pub crates: Vec<CrateDrift>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CrateDrift {
+  pub name: String,
+  // whether a single version satisfies the requirements of all workers
+  pub satisfiable: bool,
+  pub requirements: Vec<RequirementUsage>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RequirementUsage {
+  // version requirement, or the source of git and path dependencies
+  pub requirement: String,
+  pub workers: Vec<Usage>,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct Usage {
+  pub worker: String,
+  // manifest declaring the dependency
+  pub path: String,
+}
+
+impl DriftReport {
+  /// Aggregate the dependencies of the fetched manifests of all workers.
+  /// Without `all`, only the crates required with several requirements are kept.
+  pub fn new(cfg: &McaiWorkersConfig, all: bool) -> DriftReport {
+    let mut requirements: BTreeMap<String, BTreeMap<String, Vec<Usage>>> = BTreeMap::new();
+
+    for repo in &cfg.repos {
+      for (path, manifest) in cargo::manifests(repo) {
+        // unparsable manifests are reported by `list`
+        let manifest = match manifest {
+          Ok(manifest) => manifest,
+          Err(_) => continue,
+        };
+
+        let dependencies = manifest
+          .dependencies
+          .iter()
+          .chain(&manifest.build_dependencies)
+          .chain(&manifest.dev_dependencies);
+        for (name, dependency) in dependencies {
+          let (name, requirement) = match requirement(name, dependency) {
+            Some(requirement) => requirement,
+            None => continue,
+          };
+
+          let usage = Usage {
+            worker: repo.name.clone(),
+            path: path.clone(),
+          };
+          let usages = requirements
+            .entry(name)
+            .or_default()
+            .entry(requirement)
+            .or_default();
+          if !usages.contains(&usage) {
+            usages.push(usage);
+          }
+        }
+      }
+    }
+
+    let crates = requirements
+      .into_iter()
+      .filter(|(_, requirements)| all || requirements.len() > 1)
+      .map(|(name, requirements)| CrateDrift {
+        satisfiable: satisfiable(requirements.keys()),
+        name,
+        requirements: requirements
+          .into_iter()
+          .map(|(requirement, workers)| RequirementUsage {
+            requirement,
+            workers,
+          })
+          .collect(),
+      })
+      .collect();
+
+    DriftReport { crates }
+  }
+
+  pub fn render(&self, format: Format) -> String {
+    match format {
+      Format::Text => self.render_text(),
+      Format::Json => format!("{}\n", serde_json::to_string_pretty(self).unwrap()),
+      Format::Yaml => serde_yaml::to_string(self).unwrap(),
+      Format::Csv => self.render_csv(),
+      Format::Markdown => self.render_markdown(),
+    }
+  }
+
+  fn render_text(&self) -> String {
+    let mut output = String::new();
+
+    for krate in &self.crates {
+      writeln!(output).unwrap();
+      if krate.satisfiable {
+        writeln!(
+          output,
+          "{} {}",
+          Emoji("📦", &"=>".magenta().bold()),
+          krate.name.magenta().bold()
+        )
+        .unwrap();
+      } else {
+        writeln!(
+          output,
+          "{} {} {}",
+          Emoji("📦", &"=>".magenta().bold()),
+          krate.name.magenta().bold(),
+          "no version satisfies all requirements".red()
+        )
+        .unwrap();
+      }
+
+      for requirement in &krate.requirements {
+        writeln!(
+          output,
+          "  {} {} ({} worker(s))",
+          Emoji("🔗", "=>"),
+          requirement.requirement.yellow(),
+          requirement.workers.len()
+        )
+        .unwrap();
+        for usage in &requirement.workers {
+          writeln!(output, "    - {} ({})", usage.worker, usage.path).unwrap();
+        }
+      }
+    }
+
+    let unsatisfiable = self
+      .crates
+      .iter()
+      .filter(|krate| !krate.satisfiable)
+      .count();
+    writeln!(
+      output,
+      "\n{} crate(s), {} without a version satisfying all requirements",
+      self.crates.len(),
+      unsatisfiable
+    )
+    .unwrap();
+
+    output
+  }
+
+  fn render_csv(&self) -> String {
+    let mut output = csv::line(
+      ["crate", "requirement", "worker", "path", "satisfiable"]
+        .iter()
+        .map(|column| column.to_string()),
+    );
+
+    for krate in &self.crates {
+      for requirement in &krate.requirements {
+        for usage in &requirement.workers {
+          output.push_str(&csv::line(
+            vec![
+              krate.name.clone(),
+              requirement.requirement.clone(),
+              usage.worker.clone(),
+              usage.path.clone(),
+              krate.satisfiable.to_string(),
+            ]
+            .into_iter(),
+          ));
+        }
+      }
+    }
+
+    output
+  }
+
+  fn render_markdown(&self) -> String {
+    let mut output = String::from("# Dependency drift\n\n");
+    output.push_str("| Crate | Requirement | Workers |\n");
+    output.push_str("|---|---|---|\n");
+
+    for krate in &self.crates {
+      let name = if krate.satisfiable {
+        krate.name.clone()
+      } else {
+        format!("❗ **{}**", krate.name)
+      };
+
+      for requirement in &krate.requirements {
+        let workers: Vec<String> = requirement
+          .workers
+          .iter()
+          .map(|usage| markdown::escape(&format!("{} ({})", usage.worker, usage.path)))
+          .collect();
+
+        output.push_str(&format!(
+          "| {} | `{}` | {} |\n",
+          markdown::escape(&name),
+          markdown::escape(&requirement.requirement),
+          workers.join("<br>")
+        ));
+      }
+    }
+
+    output
+  }
+}
+
+/// Get the name of the required crate, which may be renamed, and its requirement
+fn requirement(name: &str, dependency: &ManifestDependency) -> Option<(String, String)> {
+  match dependency {
+    ManifestDependency::Simple(version) => Some((name.to_string(), version.clone())),
+    ManifestDependency::Detailed(detailed) => {
+      let requirement = detailed
+        .version
+        .clone()
+        .or_else(|| detailed.git.as_ref().map(|git| format!("git {}", git)))
+        .or_else(|| detailed.path.as_ref().map(|path| format!("path {}", path)))?;
+      let name = detailed.package.as_deref().unwrap_or(name);
+      Some((name.to_string(), requirement))
+    }
+    // inherited dependencies are resolved from the workspace when manifests are parsed
+    ManifestDependency::Inherited(_) => None,
+  }
+}
+
+/// Check whether a version satisfies all the requirements.
+/// The candidates are the bounds of the comparators of the requirements, the lowest and highest versions a range allows being among them.
+/// Git and path dependencies are not compared.
+fn satisfiable<'a>(requirements: impl Iterator<Item = &'a String> + Clone) -> bool {
+  let parsed: Vec<VersionReq> = requirements
+    .clone()
+    .filter_map(|requirement| cargo::requirement(requirement))
+    .collect();
+
+  let mut candidates = vec![Version::new(0, 0, 0)];
+  for requirement in requirements {
+    candidates.extend(requirement.split(',').flat_map(bounds));
+  }
+
+  candidates.iter().any(|candidate| {
+    parsed
+      .iter()
+      .all(|requirement| requirement.matches(candidate))
+  })
+}
+
+/// Get the bounds of a comparator, like `1.2.0` and `1.2.1` for `>1.2.0`,
+/// or `1.2.0` and the predecessor of `1.3.0` for `~1.2`
+fn bounds(comparator: &str) -> Vec<Version> {
+  let comparator = comparator.trim();
+  let start = comparator
+    .find(|c: char| c.is_ascii_digit())
+    .unwrap_or(comparator.len());
+  let (operator, version) = comparator.split_at(start);
+  let end = version
+    .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+    .unwrap_or(version.len());
+
+  let components = match version[..end]
+    .trim_end_matches('.')
+    .split('.')
+    .map(|component| component.parse().ok())
+    .collect::<Option<Vec<u64>>>()
+  {
+    Some(components) if components.len() <= 3 => components,
+    _ => return vec![],
+  };
+
+  let mut candidates = vec![bump(&components, None)];
+  match operator.trim() {
+    // strict lower bounds, like `>1.2` allowing `1.3.0`
+    ">" => candidates.push(bump(&components, Some(components.len() - 1))),
+    "<" => candidates.extend(predecessor(&candidates[0])),
+    "~" => {
+      let upper = bump(&components, Some(components.len().min(2) - 1));
+      candidates.extend(predecessor(&upper));
+    }
+    // requirements without operator are caret ones, bounded by their first non-zero component
+    "^" | "" => {
+      let index = components
+        .iter()
+        .position(|component| *component != 0)
+        .unwrap_or(components.len() - 1);
+      candidates.extend(predecessor(&bump(&components, Some(index))));
+    }
+    _ => {}
+  }
+  candidates.dedup();
+  candidates
+}
+
+/// Build a version from partial components, missing ones being zero,
+/// the component at `index` being incremented and the following ones reset
+fn bump(components: &[u64], index: Option<usize>) -> Version {
+  let mut version = [0; 3];
+  version[..components.len()].copy_from_slice(components);
+  if let Some(index) = index {
+    version[index] += 1;
+    for component in &mut version[index + 1..] {
+      *component = 0;
+    }
+  }
+  Version::new(version[0], version[1], version[2])
+}
+
+/// Get the highest version lower than a version, `None` for `0.0.0`
+fn predecessor(version: &Version) -> Option<Version> {
+  match (version.major, version.minor, version.patch) {
+    (0, 0, 0) => None,
+    (major, 0, 0) => Some(Version::new(major - 1, u64::MAX, u64::MAX)),
+    (major, minor, 0) => Some(Version::new(major, minor - 1, u64::MAX)),
+    (major, minor, patch) => Some(Version::new(major, minor, patch - 1)),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn satisfiable_requirements() {
+    let cases = vec![
+      (vec!["1.0"], true),
+      (vec!["1.0", "1.2"], true),
+      (vec!["^1.2", "~1.4"], true),
+      (vec!["0.11", "0.12"], false),
+      (vec!["1.0", "2.0"], false),
+      (vec![">=1.0, <1.5", "1.5"], false),
+      (vec![">=1.0, <2", "1.5"], true),
+      (vec!["=1.2.3", "1.2"], true),
+      (vec!["=1.2.3", "=1.2.4"], false),
+      (vec!["*", "0.3"], true),
+      (vec!["<1", "0.5"], true),
+      (vec![">1.2.0"], true),
+      (vec![">1.2.0, <1.3"], true),
+      (vec![">1.2.0", "1.2"], true),
+      (vec![">1.2.0", "=1.2.0"], false),
+      (vec!["<1.3", "1.2.5"], true),
+      (vec![">=1.2, <1.2.1", "~1.2.0"], true),
+      (
+        vec![
+          "1.0",
+          "git https://github.com/media-cloud-ai/mcai_worker_sdk",
+        ],
+        true,
+      ),
+      (
+        vec![
+          "path ../sdk",
+          "git https://github.com/media-cloud-ai/mcai_worker_sdk",
+        ],
+        true,
+      ),
+    ];
+
+    for (requirements, expected) in cases {
+      let requirements: Vec<String> = requirements.into_iter().map(String::from).collect();
+      assert_eq!(
+        satisfiable(requirements.iter()),
+        expected,
+        "{:?}",
+        requirements
+      );
+    }
+  }
+
+  #[test]
+  fn comparator_bounds() {
+    let max = u64::MAX;
+    let cases = vec![
+      ("1.2.3", vec![(1, 2, 3), (1, max, max)]),
+      ("0.2", vec![(0, 2, 0), (0, 2, max)]),
+      ("^0.0.3", vec![(0, 0, 3)]),
+      (">1.2.0", vec![(1, 2, 0), (1, 2, 1)]),
+      (">1.2", vec![(1, 2, 0), (1, 3, 0)]),
+      (">=1.2", vec![(1, 2, 0)]),
+      ("<1.3", vec![(1, 3, 0), (1, 2, max)]),
+      ("<1", vec![(1, 0, 0), (0, max, max)]),
+      ("~1.4", vec![(1, 4, 0), (1, 4, max)]),
+      ("~1", vec![(1, 0, 0), (1, max, max)]),
+      ("=1.2.3", vec![(1, 2, 3)]),
+      ("1.*", vec![(1, 0, 0), (1, max, max)]),
+      ("*", vec![]),
+      ("1.2.3.4", vec![]),
+    ];
+
+    for (comparator, expected) in cases {
+      let expected: Vec<Version> = expected
+        .into_iter()
+        .map(|(major, minor, patch)| Version::new(major, minor, patch))
+        .collect();
+      assert_eq!(bounds(comparator), expected, "{}", comparator);
+    }
+  }
+}
@@ -0,0 +1,150 @@
+use super::{image_name, Report, SdkRequirement, WorkerReport};
+use crate::config::short_commit;
+
+/// Render a report as a Markdown table, with a row per worker
+pub fn render(report: &Report) -> String {
+  let mut output = String::from("# MCAI workers\n\n");
+
+  let expected: Vec<String> = [
+    ("MCAI Worker SDK", &report.mcai_sdk_version),
+    ("Rust", &report.rust_version),
+  ]
+  .iter()
+  .filter_map(|(name, version)| {
+    version
+      .as_ref()
+      .map(|version| format!("{} {}", name, version))
+  })
+  .collect();
+  if !expected.is_empty() {
+    output.push_str(&format!("Expected versions: {}\n\n", expected.join(", ")));
+  }
+
+  output.push_str(
+    "| Repository | Commit | Projects | MCAI Worker SDK | Rust | Docker images | Status |\n",
+  );
+  output.push_str("|---|---|---|---|---|---|---|\n");
+
+  for worker in &report.workers {
+    let cells = [
+      worker.name.clone(),
+      worker
+        .commit
+        .as_deref()
+        .map(|commit| format!("`{}`", short_commit(commit)))
+        .unwrap_or_default(),
+      projects(worker),
+      sdk_requirements(worker),
+      rust_versions(worker),
+      images(worker),
+      status(worker),
+    ];
+
+    let cells: Vec<String> = cells.iter().map(|cell| escape(cell)).collect();
+    output.push_str(&format!("| {} |\n", cells.join(" | ")));
+  }
+
+  output
+}
+
+fn projects(worker: &WorkerReport) -> String {
+  let rust_projects =
+    worker
+      .rust_projects
+      .iter()
+      .map(|project| match (&project.name, &project.version) {
+        (Some(name), Some(version)) => format!("{} v{}", name, version),
+        _ => format!("{} (workspace)", project.path),
+      });
+  let python_projects = worker
+    .python_projects
+    .iter()
+    .map(|project| format!("{} (Python)", project.path));
+
+  rust_projects
+    .chain(python_projects)
+    .collect::<Vec<String>>()
+    .join("<br>")
+}
+
+fn sdk_requirements(worker: &WorkerReport) -> String {
+  let requirement = |sdk: &SdkRequirement| match &sdk.locked {
+    Some(locked) => format!("{} (locked {})", sdk.requirement, locked),
+    None => sdk.requirement.clone(),
+  };
+
+  worker
+    .rust_projects
+    .iter()
+    .filter_map(|project| project.mcai_sdk.as_ref())
+    .chain(
+      worker
+        .python_projects
+        .iter()
+        .map(|project| &project.mcai_sdk),
+    )
+    .map(requirement)
+    .collect::<Vec<String>>()
+    .join("<br>")
+}
+
+fn rust_versions(worker: &WorkerReport) -> String {
+  let rust_versions = worker
+    .rust_projects
+    .iter()
+    .filter_map(|project| project.rust_version.as_ref())
+    .map(|rust_version| format!("rust-version {}", rust_version.version));
+  let toolchains = worker
+    .toolchains
+    .iter()
+    .map(|toolchain| format!("toolchain {}", toolchain.channel.version));
+
+  rust_versions
+    .chain(toolchains)
+    .collect::<Vec<String>>()
+    .join("<br>")
+}
+
+fn images(worker: &WorkerReport) -> String {
+  worker
+    .dockerfiles
+    .iter()
+    .map(|dockerfile| {
+      dockerfile
+        .images
+        .iter()
+        .map(|image| format!("{} {}", image_name(&image.image), image.tag))
+        .collect::<Vec<String>>()
+        .join(" / ")
+    })
+    .filter(|images| !images.is_empty())
+    .collect::<Vec<String>>()
+    .join("<br>")
+}
+
+fn status(worker: &WorkerReport) -> String {
+  let mut status = vec![];
+  if let Some(error) = &worker.last_error {
+    status.push(format!("⚠️ Last fetch failed: {}", error));
+  }
+  if !worker.errors.is_empty() {
+    status.push(format!("❗ {} invalid file(s)", worker.errors.len()));
+  }
+  if worker.update_required() {
+    status.push("❗ Update required".to_string());
+  }
+  if !worker.findings.is_empty() {
+    status.push(format!("🛡️ {} advisory(ies)", worker.findings.len()));
+  }
+
+  if status.is_empty() {
+    "✅ Up to date".to_string()
+  } else {
+    status.join("<br>")
+  }
+}
+
+/// Escape the characters of a table cell
+pub fn escape(cell: &str) -> String {
+  cell.replace('|', "\\|").replace('\n', " ")
+}
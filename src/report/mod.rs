@@ -0,0 +1,509 @@
+mod audit;
+mod csv;
+mod drift;
+mod markdown;
+mod outdated;
+mod text;
+
+use crate::{
+  audit::{Auditor, Finding},
+  cargo,
+  config::{FileKind, McaiWorkersConfig, RepoConfig},
+  error::Result,
+  python, rust,
+  version::{self, Lag},
+};
+use cargo_toml::{Dependency as ManifestDependency, Manifest};
+use dockerfile_parser::{Dockerfile, ImageRef, Instruction};
+use semver::{Version, VersionReq};
+
+pub use audit::AuditReport;
+pub use drift::DriftReport;
+pub use outdated::OutdatedReport;
+
+pub static FORMATS: &[&str] = &["text", "json", "yaml", "csv", "markdown"];
+
+/// Format a report is rendered in
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Format {
+  Text,
+  Json,
+  Yaml,
+  Csv,
+  Markdown,
+}
+
+impl From<&str> for Format {
+  fn from(value: &str) -> Self {
+    match value {
+      "text" => Format::Text,
+      "json" => Format::Json,
+      "yaml" => Format::Yaml,
+      "csv" => Format::Csv,
+      "markdown" => Format::Markdown,
+      _ => panic!("Invalid format"),
+    }
+  }
+}
+
+/// Analysis of the fetched files of all registered workers
+#[derive(Debug, Serialize)]
+pub struct Report {
+  // expected versions, as set with the `versions` command
+  pub rust_version: Option<String>,
+  pub mcai_sdk_version: Option<String>,
+  pub workers: Vec<WorkerReport>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct WorkerReport {
+  pub name: String,
+  pub provider: String,
+  pub reference: Option<String>,
+  pub commit: Option<String>,
+  // error of the last fetch, the report is built from the previously fetched files
+  pub last_error: Option<String>,
+  // files which can not be parsed
+  pub errors: Vec<FileError>,
+  pub rust_projects: Vec<RustProject>,
+  pub python_projects: Vec<PythonProject>,
+  pub toolchains: Vec<Toolchain>,
+  pub dockerfiles: Vec<DockerfileReport>,
+  // advisories and yanked versions of the locked packages, when audited
+  #[serde(skip_serializing_if = "Vec::is_empty")]
+  pub findings: Vec<Finding>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FileError {
+  pub path: String,
+  pub error: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RustProject {
+  pub path: String,
+  // name and version of the package, missing for virtual workspace manifests
+  pub name: Option<String>,
+  pub version: Option<String>,
+  // number of members of the workspace declared by the manifest, among the fetched manifests
+  pub workspace_members: Option<usize>,
+  pub rust_version: Option<RustVersion>,
+  pub mcai_sdk: Option<SdkRequirement>,
+  #[serde(skip_serializing_if = "Vec::is_empty")]
+  pub dependencies: Vec<Dependency>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PythonProject {
+  pub path: String,
+  pub mcai_sdk: SdkRequirement,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Toolchain {
+  pub path: String,
+  pub channel: RustVersion,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DockerfileReport {
+  pub path: String,
+  pub images: Vec<Image>,
+  pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Image {
+  pub image: String,
+  pub tag: String,
+  // whether the image is a Rust image older than the expected version
+  pub update_required: bool,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub lag: Option<Lag>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RustVersion {
+  pub version: String,
+  pub update_required: bool,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub lag: Option<Lag>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SdkRequirement {
+  pub requirement: String,
+  pub locked: Option<String>,
+  // name of the lockfile, which does not satisfy the requirement anymore when stale
+  pub lockfile: String,
+  pub stale_lockfile: bool,
+  pub lockfile_error: Option<String>,
+  pub update_required: bool,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub lag: Option<Lag>,
+}
+
+/// Version of a worker behind the expected one
+#[derive(Debug, Serialize)]
+pub struct Violation {
+  pub path: String,
+  pub description: String,
+  pub lag: Lag,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Dependency {
+  pub name: String,
+  pub requirement: String,
+}
+
+impl Report {
+  /// Analyze the fetched files of all workers.
+  /// With `dependencies`, the dependencies of the Rust projects are listed.
+  /// With `auditor`, the locked packages are checked against security advisories.
+  pub fn new(cfg: &McaiWorkersConfig, dependencies: bool, auditor: Option<&Auditor>) -> Report {
+    Report {
+      rust_version: cfg.rust_version.as_ref().map(|version| version.to_string()),
+      mcai_sdk_version: cfg
+        .mcai_sdk_version
+        .as_ref()
+        .map(|version| version.to_string()),
+      workers: cfg
+        .repos
+        .iter()
+        .map(|repo| WorkerReport::new(cfg, repo, dependencies, auditor))
+        .collect(),
+    }
+  }
+
+  pub fn render(&self, format: Format) -> String {
+    match format {
+      Format::Text => text::render(self),
+      Format::Json => format!("{}\n", serde_json::to_string_pretty(self).unwrap()),
+      Format::Yaml => serde_yaml::to_string(self).unwrap(),
+      Format::Csv => csv::render(self),
+      Format::Markdown => markdown::render(self),
+    }
+  }
+}
+
+impl WorkerReport {
+  fn new(
+    cfg: &McaiWorkersConfig,
+    repo: &RepoConfig,
+    dependencies: bool,
+    auditor: Option<&Auditor>,
+  ) -> WorkerReport {
+    let mut report = WorkerReport {
+      name: repo.name.clone(),
+      provider: repo.provider.as_str().to_string(),
+      reference: repo.fetched.fetched_reference.clone(),
+      commit: repo.fetched.commit.clone(),
+      last_error: repo.fetched.last_error.clone(),
+      errors: vec![],
+      rust_projects: vec![],
+      python_projects: vec![],
+      toolchains: vec![],
+      dockerfiles: vec![],
+      findings: vec![],
+    };
+
+    for (path, manifest) in cargo::manifests(repo) {
+      let manifest = match manifest {
+        Ok(manifest) => manifest,
+        Err(error) => {
+          report.errors.push(FileError {
+            path,
+            error: error.to_string(),
+          });
+          continue;
+        }
+      };
+
+      let mcai_sdk = mcai_worker_sdk_version(&manifest).map(|version| {
+        let requirement = cargo::requirement(&version);
+        let locked = cargo::lockfile(repo, &path).map(|lockfile| {
+          Ok(lockfile?.locked_version(|name| name == "mcai_worker_sdk", requirement.as_ref()))
+        });
+
+        SdkRequirement::new(
+          cfg,
+          &version,
+          requirement.as_ref(),
+          locked.and_then(Result::transpose),
+          "Cargo.lock",
+        )
+      });
+
+      let package = manifest.package.as_ref();
+      let workspace_members = manifest
+        .workspace
+        .as_ref()
+        .map(|_| cargo::workspace_member_count(repo, &path));
+      report.rust_projects.push(RustProject {
+        path,
+        name: package.map(|package| package.name.clone()),
+        version: package.map(|package| package.version().to_string()),
+        workspace_members,
+        rust_version: package
+          .and_then(|package| package.rust_version())
+          .map(|version| RustVersion::new(cfg, version)),
+        mcai_sdk,
+        dependencies: if dependencies {
+          manifest
+            .dependencies
+            .iter()
+            .map(|(name, requirement)| Dependency {
+              name: name.clone(),
+              requirement: match requirement {
+                ManifestDependency::Simple(version) => version.to_string(),
+                requirement => format!("{:?}", requirement),
+              },
+            })
+            .collect()
+        } else {
+          vec![]
+        },
+      });
+    }
+
+    for sdk_requirement in python::sdk_requirements(repo) {
+      let specifiers = if sdk_requirement.specifiers.is_empty() {
+        "*"
+      } else {
+        &sdk_requirement.specifiers
+      };
+
+      let mcai_sdk = SdkRequirement::new(
+        cfg,
+        specifiers,
+        sdk_requirement.requirement.as_ref(),
+        python::locked_sdk_version(repo, &sdk_requirement),
+        "poetry.lock",
+      );
+      report.python_projects.push(PythonProject {
+        path: sdk_requirement.path,
+        mcai_sdk,
+      });
+    }
+
+    for toolchain in rust::toolchains(repo) {
+      report.toolchains.push(Toolchain {
+        path: toolchain.path,
+        channel: RustVersion::new(cfg, &toolchain.channel),
+      });
+    }
+
+    for file in repo.files(FileKind::Dockerfile) {
+      let (images, error) = match Dockerfile::parse(&file.content) {
+        Ok(dockerfile) => (images(cfg, &dockerfile), None),
+        Err(error) => (vec![], Some(error.to_string())),
+      };
+
+      report.dockerfiles.push(DockerfileReport {
+        path: file.path.clone(),
+        images,
+        error,
+      });
+    }
+
+    if let Some(auditor) = auditor {
+      let (findings, errors) = auditor.audit(repo);
+      report.findings = findings;
+      report.errors.extend(
+        errors
+          .into_iter()
+          .map(|(path, error)| FileError { path, error }),
+      );
+    }
+
+    report
+  }
+
+  /// Whether any part of the worker requires an update
+  pub fn update_required(&self) -> bool {
+    !self.violations().is_empty()
+  }
+
+  /// Get the versions of the worker behind the expected ones
+  pub fn violations(&self) -> Vec<Violation> {
+    let mut violations = vec![];
+
+    for project in &self.rust_projects {
+      if let Some(sdk @ SdkRequirement { lag: Some(lag), .. }) = &project.mcai_sdk {
+        violations.push(Violation {
+          path: project.path.clone(),
+          description: sdk.description(),
+          lag: *lag,
+        });
+      }
+      if let Some(RustVersion {
+        version,
+        lag: Some(lag),
+        ..
+      }) = &project.rust_version
+      {
+        violations.push(Violation {
+          path: project.path.clone(),
+          description: format!("rust-version {}", version),
+          lag: *lag,
+        });
+      }
+    }
+
+    for project in &self.python_projects {
+      if let Some(lag) = project.mcai_sdk.lag {
+        violations.push(Violation {
+          path: project.path.clone(),
+          description: project.mcai_sdk.description(),
+          lag,
+        });
+      }
+    }
+
+    for toolchain in &self.toolchains {
+      if let Some(lag) = toolchain.channel.lag {
+        violations.push(Violation {
+          path: toolchain.path.clone(),
+          description: format!("Rust channel {}", toolchain.channel.version),
+          lag,
+        });
+      }
+    }
+
+    for dockerfile in &self.dockerfiles {
+      for image in &dockerfile.images {
+        if let Some(lag) = image.lag {
+          violations.push(Violation {
+            path: dockerfile.path.clone(),
+            description: format!("image {}:{}", image.image, image.tag),
+            lag,
+          });
+        }
+      }
+    }
+
+    violations
+  }
+}
+
+impl RustVersion {
+  fn new(cfg: &McaiWorkersConfig, version: &str) -> RustVersion {
+    let lag = rust_lag(cfg, version);
+    RustVersion {
+      version: version.to_string(),
+      update_required: lag.is_some(),
+      lag,
+    }
+  }
+}
+
+impl SdkRequirement {
+  fn new(
+    cfg: &McaiWorkersConfig,
+    specifiers: &str,
+    requirement: Option<&VersionReq>,
+    locked: Option<Result<(Version, bool)>>,
+    lockfile: &str,
+  ) -> SdkRequirement {
+    let (locked, lockfile_error) = match locked {
+      Some(Ok(locked)) => (Some(locked), None),
+      Some(Err(error)) => (None, Some(error.to_string())),
+      None => (None, None),
+    };
+
+    let lag = cfg.mcai_sdk_version.as_ref().and_then(|mcai_sdk_version| {
+      // workers are built with the locked version, the requirement is checked without lockfile
+      match &locked {
+        Some((locked_version, _)) => version::lag(&locked_version.to_string(), mcai_sdk_version),
+        None => requirement.and_then(|requirement| {
+          version::requirement_lag(specifiers, requirement, mcai_sdk_version)
+        }),
+      }
+    });
+
+    SdkRequirement {
+      requirement: specifiers.to_string(),
+      stale_lockfile: matches!(locked, Some((_, false))),
+      locked: locked.map(|(locked_version, _)| locked_version.to_string()),
+      lockfile: lockfile.to_string(),
+      lockfile_error,
+      update_required: lag.is_some(),
+      lag,
+    }
+  }
+
+  fn description(&self) -> String {
+    match &self.locked {
+      Some(locked) => format!("MCAI Worker SDK {} (locked {})", self.requirement, locked),
+      None => format!("MCAI Worker SDK {}", self.requirement),
+    }
+  }
+}
+
+fn rust_lag(cfg: &McaiWorkersConfig, version: &str) -> Option<Lag> {
+  cfg
+    .rust_version
+    .as_ref()
+    .and_then(|rust_version| version::lag(version, rust_version))
+}
+
+fn mcai_worker_sdk_version(manifest: &Manifest) -> Option<String> {
+  for (name, version) in &manifest.dependencies {
+    if name == "mcai_worker_sdk" {
+      return match version {
+        ManifestDependency::Simple(version) => Some(version.to_string()),
+        ManifestDependency::Detailed(detailed) => {
+          if let Some(version) = &detailed.version {
+            Some(version.to_string())
+          } else {
+            Some(detailed.path.clone().unwrap_or_default())
+          }
+        }
+        // inherited dependencies are resolved from the workspace when manifests are parsed
+        ManifestDependency::Inherited(_) => None,
+      };
+    }
+  }
+  None
+}
+
+fn images(cfg: &McaiWorkersConfig, dockerfile: &Dockerfile) -> Vec<Image> {
+  dockerfile
+    .instructions
+    .iter()
+    .filter_map(|instruction| match instruction {
+      Instruction::From(content) => Some(&content.image_parsed),
+      _ => None,
+    })
+    .map(|image: &ImageRef| {
+      let tag = image.tag.clone().unwrap_or_else(|| "latest".to_string());
+      let lag = if rust::is_rust_image(&image.image) {
+        rust_lag(cfg, &tag)
+      } else {
+        None
+      };
+      Image {
+        update_required: lag.is_some(),
+        lag,
+        image: image.image.clone(),
+        tag,
+      }
+    })
+    .collect()
+}
+
+/// Get the display name of an image
+pub fn image_name(image: &str) -> &str {
+  match image {
+    "rust" => "Rust",
+    "ubuntu" => "Ubuntu",
+    "debian" => "Debian",
+    "mediacloudai/rs_command_line_worker" => "Command Line",
+    "mediacloudai/py_mcai_worker_sdk" => "Python MCAI SDK",
+    "mediacloudai/c_mcai_worker_sdk" => "C MCAI SDK",
+    "mediacloudai/docker_alpine_ffmpeg" => "Alpine FFmpeg",
+    image_name => image_name,
+  }
+}
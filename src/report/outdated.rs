@@ -0,0 +1,406 @@
+use super::{csv, markdown, Format};
+use crate::{
+  cargo,
+  config::McaiWorkersConfig,
+  index::Index,
+  parallel,
+  version::{self, Lag},
+};
+use cargo_toml::Dependency as ManifestDependency;
+use colored::Colorize;
+use console::Emoji;
+use semver::Version;
+use std::{
+  collections::{BTreeMap, BTreeSet},
+  fmt::Write,
+};
+
+/// Dependencies of the Rust projects behind the newest versions published in an index
+#[derive(Debug, Serialize)]
+pub struct OutdatedReport {
+  pub index: String,
+  pub workers: Vec<WorkerOutdated>,
+  // crates which can not be looked up in the index
+  pub errors: Vec<LookupError>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct WorkerOutdated {
+  pub name: String,
+  pub dependencies: Vec<OutdatedDependency>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct OutdatedDependency {
+  // manifest declaring the dependency
+  pub path: String,
+  pub name: String,
+  pub requirement: String,
+  pub locked: Option<String>,
+  pub latest: String,
+  pub lag: Lag,
+}
+
+#[derive(Debug, Serialize)]
+pub struct LookupError {
+  pub name: String,
+  pub error: String,
+}
+
+impl OutdatedReport {
+  /// Compare the dependencies of the fetched manifests of all workers with the newest versions of the index,
+  /// looking up crates with at most `jobs` concurrent requests
+  pub fn new(
+    cfg: &McaiWorkersConfig,
+    index_url: &str,
+    index: &Index,
+    jobs: usize,
+  ) -> OutdatedReport {
+    // dependencies of each worker, as manifest path, crate name, requirement and locked version
+    let mut dependencies = vec![];
+    for repo in &cfg.repos {
+      let mut worker_dependencies = vec![];
+      for (path, manifest) in cargo::manifests(repo) {
+        // unparsable manifests are reported by `list`
+        let manifest = match manifest {
+          Ok(manifest) => manifest,
+          Err(_) => continue,
+        };
+        let lockfile = cargo::lockfile(repo, &path).and_then(Result::ok);
+
+        let registry_dependencies = manifest
+          .dependencies
+          .iter()
+          .chain(&manifest.build_dependencies)
+          .chain(&manifest.dev_dependencies)
+          .filter_map(|(name, dependency)| registry_requirement(name, dependency));
+        for (name, requirement) in registry_dependencies {
+          let locked = lockfile.as_ref().and_then(|lockfile| {
+            let parsed = cargo::requirement(&requirement);
+            lockfile
+              .locked_version(|package| package == name, parsed.as_ref())
+              .map(|(locked, _)| locked)
+          });
+          // crates are often required the same way as dependency and development dependency
+          let dependency = (path.clone(), name, requirement, locked);
+          if !worker_dependencies.contains(&dependency) {
+            worker_dependencies.push(dependency);
+          }
+        }
+      }
+      dependencies.push((repo.name.clone(), worker_dependencies));
+    }
+
+    let names: Vec<String> = dependencies
+      .iter()
+      .flat_map(|(_, worker_dependencies)| worker_dependencies)
+      .map(|(_, name, _, _)| name.clone())
+      .collect::<BTreeSet<String>>()
+      .into_iter()
+      .collect();
+    let lookups = parallel::map(&names, jobs, |name| index.latest_version(name));
+
+    let mut latest_versions = BTreeMap::new();
+    let mut errors = vec![];
+    for (name, lookup) in names.into_iter().zip(lookups) {
+      match lookup {
+        Ok(Some(latest)) => {
+          latest_versions.insert(name, latest);
+        }
+        Ok(None) => errors.push(LookupError {
+          name,
+          error: "not found in the index".to_string(),
+        }),
+        Err(error) => errors.push(LookupError {
+          name,
+          error: error.to_string(),
+        }),
+      }
+    }
+
+    let workers = dependencies
+      .into_iter()
+      .map(|(name, worker_dependencies)| WorkerOutdated {
+        name,
+        dependencies: worker_dependencies
+          .into_iter()
+          .filter_map(|(path, name, requirement, locked)| {
+            let latest = latest_versions.get(&name)?;
+            let lag = lag(&requirement, locked.as_ref(), latest)?;
+            Some(OutdatedDependency {
+              path,
+              name,
+              requirement,
+              locked: locked.map(|locked| locked.to_string()),
+              latest: latest.to_string(),
+              lag,
+            })
+          })
+          .collect(),
+      })
+      .collect();
+
+    OutdatedReport {
+      index: index_url.to_string(),
+      workers,
+      errors,
+    }
+  }
+
+  pub fn render(&self, format: Format) -> String {
+    match format {
+      Format::Text => self.render_text(),
+      Format::Json => format!("{}\n", serde_json::to_string_pretty(self).unwrap()),
+      Format::Yaml => serde_yaml::to_string(self).unwrap(),
+      Format::Csv => self.render_csv(),
+      Format::Markdown => self.render_markdown(),
+    }
+  }
+
+  fn render_text(&self) -> String {
+    let mut output = String::new();
+
+    for worker in &self.workers {
+      if worker.dependencies.is_empty() {
+        continue;
+      }
+
+      writeln!(output).unwrap();
+      writeln!(
+        output,
+        "{} {}",
+        Emoji("🚀", &"=>".green().bold()),
+        worker.name.green().bold()
+      )
+      .unwrap();
+
+      for dependency in &worker.dependencies {
+        let lock = match &dependency.locked {
+          Some(locked) => format!(" (locked {})", locked),
+          None => String::new(),
+        };
+        let message = format!(
+          "{} {}{}, latest {} ({} lag)",
+          dependency.name,
+          dependency.requirement,
+          lock,
+          dependency.latest,
+          dependency.lag.as_str()
+        );
+
+        let message = match dependency.lag {
+          Lag::Major => message.red(),
+          Lag::Minor => message.yellow(),
+          Lag::Patch => message.normal(),
+        };
+        writeln!(
+          output,
+          "  {} {}: {}",
+          Emoji("📦", "=>"),
+          dependency.path,
+          message
+        )
+        .unwrap();
+      }
+    }
+
+    if !self.errors.is_empty() {
+      writeln!(output).unwrap();
+    }
+    for error in &self.errors {
+      writeln!(
+        output,
+        "{} {}: {}",
+        Emoji("❗", "=>"),
+        error.name,
+        error.error.red()
+      )
+      .unwrap();
+    }
+
+    let count = |lag: Lag| {
+      self
+        .workers
+        .iter()
+        .flat_map(|worker| &worker.dependencies)
+        .filter(|dependency| dependency.lag == lag)
+        .count()
+    };
+    writeln!(
+      output,
+      "\nOutdated dependencies against {}: {} major, {} minor, {} patch",
+      self.index,
+      count(Lag::Major),
+      count(Lag::Minor),
+      count(Lag::Patch)
+    )
+    .unwrap();
+
+    output
+  }
+
+  fn render_csv(&self) -> String {
+    let mut output = csv::line(
+      [
+        "repository",
+        "path",
+        "crate",
+        "requirement",
+        "locked",
+        "latest",
+        "lag",
+      ]
+      .iter()
+      .map(|column| column.to_string()),
+    );
+
+    for worker in &self.workers {
+      for dependency in &worker.dependencies {
+        output.push_str(&csv::line(
+          vec![
+            worker.name.clone(),
+            dependency.path.clone(),
+            dependency.name.clone(),
+            dependency.requirement.clone(),
+            dependency.locked.clone().unwrap_or_default(),
+            dependency.latest.clone(),
+            dependency.lag.as_str().to_string(),
+          ]
+          .into_iter(),
+        ));
+      }
+    }
+
+    output
+  }
+
+  fn render_markdown(&self) -> String {
+    let mut output = String::from("# Outdated dependencies\n\n");
+    output.push_str(&format!("Index: {}\n\n", self.index));
+    output.push_str("| Repository | Manifest | Crate | Requirement | Locked | Latest | Lag |\n");
+    output.push_str("|---|---|---|---|---|---|---|\n");
+
+    for worker in &self.workers {
+      for dependency in &worker.dependencies {
+        let lag = match dependency.lag {
+          Lag::Major => format!("❗ {}", dependency.lag.as_str()),
+          lag => lag.as_str().to_string(),
+        };
+
+        output.push_str(&format!(
+          "| {} | {} | {} | `{}` | {} | {} | {} |\n",
+          markdown::escape(&worker.name),
+          markdown::escape(&dependency.path),
+          dependency.name,
+          markdown::escape(&dependency.requirement),
+          dependency.locked.as_deref().unwrap_or_default(),
+          dependency.latest,
+          lag
+        ));
+      }
+    }
+
+    output
+  }
+}
+
+/// Get the name of a crate required from the default registry, which may be renamed, and its requirement.
+/// Git, path and other registries dependencies are skipped.
+fn registry_requirement(name: &str, dependency: &ManifestDependency) -> Option<(String, String)> {
+  match dependency {
+    ManifestDependency::Simple(version) => Some((name.to_string(), version.clone())),
+    ManifestDependency::Detailed(detailed) => {
+      if detailed.git.is_some()
+        || detailed.path.is_some()
+        || detailed.registry.is_some()
+        || detailed.registry_index.is_some()
+      {
+        return None;
+      }
+      let name = detailed.package.as_deref().unwrap_or(name);
+      Some((name.to_string(), detailed.version.clone()?))
+    }
+    // inherited dependencies are resolved from the workspace when manifests are parsed
+    ManifestDependency::Inherited(_) => None,
+  }
+}
+
+/// Get the lag of a dependency behind the latest version,
+/// from its locked version or, without lockfile, from its requirement
+fn lag(requirement: &str, locked: Option<&Version>, latest: &Version) -> Option<Lag> {
+  match locked {
+    Some(locked) => version::lag(&locked.to_string(), latest),
+    None => cargo::requirement(requirement)
+      .and_then(|parsed| version::requirement_lag(requirement, &parsed, latest)),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn registry_requirements() {
+    let dependencies: BTreeMap<String, ManifestDependency> = toml::from_str(
+      r#"
+      serde = "1.0"
+      tokio = { version = "0.2", features = ["full"] }
+      sdk = { package = "mcai_worker_sdk", version = "^1.0" }
+      lapin = { git = "https://github.com/amqp-rs/lapin", version = "1.0" }
+      local = { path = "../local", version = "0.1" }
+      private = { registry = "private", version = "0.1" }
+      unversioned = { git = "https://github.com/media-cloud-ai/unversioned" }
+      "#,
+    )
+    .unwrap();
+
+    let cases = vec![
+      ("serde", Some(("serde", "1.0"))),
+      ("tokio", Some(("tokio", "0.2"))),
+      ("sdk", Some(("mcai_worker_sdk", "^1.0"))),
+      ("lapin", None),
+      ("local", None),
+      ("private", None),
+      ("unversioned", None),
+    ];
+    for (name, expected) in cases {
+      assert_eq!(
+        registry_requirement(name, &dependencies[name]),
+        expected.map(|(name, requirement)| (name.to_string(), requirement.to_string())),
+        "{}",
+        name
+      );
+    }
+  }
+
+  #[test]
+  fn dependency_lags() {
+    let latest = Version::parse("1.4.2").unwrap();
+    let cases = vec![
+      // from the locked version
+      ("1.0", Some("1.4.2"), None),
+      ("1.0", Some("1.4.1"), Some(Lag::Patch)),
+      ("1.0", Some("1.3.9"), Some(Lag::Minor)),
+      ("0.9", Some("0.9.4"), Some(Lag::Major)),
+      // from the requirement, without lockfile
+      ("1.4", None, None),
+      ("1", None, None),
+      (">=1.0", None, None),
+      ("~1.3", None, Some(Lag::Minor)),
+      ("=1.4.1", None, Some(Lag::Patch)),
+      ("0.9", None, Some(Lag::Major)),
+      ("not a requirement", None, None),
+    ];
+
+    for (requirement, locked, expected) in cases {
+      let locked = locked.map(|locked| Version::parse(locked).unwrap());
+      assert_eq!(
+        lag(requirement, locked.as_ref(), &latest),
+        expected,
+        "{} {:?}",
+        requirement,
+        locked
+      );
+    }
+  }
+}
@@ -0,0 +1,259 @@
+use super::{image_name, Report, RustVersion, SdkRequirement, WorkerReport};
+use crate::audit::Finding;
+use colored::Colorize;
+use console::Emoji;
+use std::fmt::Write;
+
+/// Render a report as colored lines for terminals
+pub fn render(report: &Report) -> String {
+  let mut output = String::new();
+  for worker in &report.workers {
+    render_worker(&mut output, report, worker).unwrap();
+  }
+  output
+}
+
+fn render_worker(output: &mut String, report: &Report, worker: &WorkerReport) -> std::fmt::Result {
+  writeln!(output)?;
+  writeln!(
+    output,
+    "{} {}",
+    Emoji("🚀", &"=>".green().bold()),
+    worker.name.green().bold()
+  )?;
+
+  if let (Some(reference), Some(commit)) = (&worker.reference, &worker.commit) {
+    writeln!(
+      output,
+      "  {} {} @ {}",
+      Emoji("🔖", &"=>".blue().bold()),
+      reference.blue(),
+      commit
+    )?;
+  }
+
+  if let Some(error) = &worker.last_error {
+    writeln!(
+      output,
+      "  {} {} {}",
+      Emoji("⚠️", &"=>".red().bold()),
+      "Last fetch failed:".red(),
+      error
+    )?;
+  }
+
+  for error in &worker.errors {
+    writeln!(
+      output,
+      "  {} {}: {}",
+      Emoji("❗", "=>"),
+      error.path,
+      error.error.red()
+    )?;
+  }
+
+  for project in &worker.rust_projects {
+    if let (Some(name), Some(version)) = (&project.name, &project.version) {
+      writeln!(
+        output,
+        "  {} {} {} {}{} ({})",
+        Emoji("📙", &"=>".magenta().bold()),
+        "Rust project".yellow(),
+        name.yellow(),
+        "v".yellow(),
+        version.yellow(),
+        project.path
+      )?;
+
+      if let Some(rust_version) = &project.rust_version {
+        render_rust_version(output, report, "rust-version", rust_version)?;
+      }
+    } else if let Some(members) = project.workspace_members {
+      writeln!(
+        output,
+        "  {} {} {} ({} member(s))",
+        Emoji("📚", &"=>".magenta().bold()),
+        "Rust workspace".yellow(),
+        project.path.yellow(),
+        members
+      )?;
+    }
+
+    if let Some(sdk) = &project.mcai_sdk {
+      render_sdk_requirement(output, report, sdk)?;
+    }
+  }
+
+  for project in &worker.python_projects {
+    writeln!(
+      output,
+      "  {} {} {}",
+      Emoji("🐍", &"=>".magenta().bold()),
+      "Python project".yellow(),
+      project.path.yellow()
+    )?;
+    render_sdk_requirement(output, report, &project.mcai_sdk)?;
+  }
+
+  for toolchain in &worker.toolchains {
+    writeln!(
+      output,
+      "  {} {} {}",
+      Emoji("🦀", &"=>".yellow().bold()),
+      "Rust toolchain".yellow(),
+      toolchain.path.yellow()
+    )?;
+    render_rust_version(output, report, "Rust channel", &toolchain.channel)?;
+  }
+
+  for dockerfile in &worker.dockerfiles {
+    if let Some(error) = &dockerfile.error {
+      writeln!(
+        output,
+        "  {} {}: {}",
+        Emoji("🐳", "=>"),
+        dockerfile.path,
+        error.red()
+      )?;
+      continue;
+    }
+
+    if !dockerfile.images.is_empty() {
+      let images: Vec<String> = dockerfile
+        .images
+        .iter()
+        .map(|image| format!("{} {}", image_name(&image.image), image.tag))
+        .collect();
+
+      writeln!(
+        output,
+        "  {} {} ({})",
+        Emoji("🐳", &"=>".cyan().bold()),
+        images.join(" // ").cyan(),
+        dockerfile.path
+      )?;
+    }
+
+    for image in dockerfile
+      .images
+      .iter()
+      .filter(|image| image.update_required)
+    {
+      writeln!(
+        output,
+        "    {} {}",
+        rust_update(report).red(),
+        format!("(rust:{})", image.tag).red()
+      )?;
+    }
+  }
+
+  for finding in &worker.findings {
+    render_finding(output, finding)?;
+  }
+
+  for project in &worker.rust_projects {
+    for dependency in &project.dependencies {
+      writeln!(
+        output,
+        "  - {} ({})",
+        dependency.name, dependency.requirement
+      )?;
+    }
+  }
+
+  Ok(())
+}
+
+/// Render an advisory or a yanked version of a locked package
+pub fn render_finding(output: &mut String, finding: &Finding) -> std::fmt::Result {
+  let message = format!(
+    "{} {} {}",
+    finding.package,
+    finding.version,
+    finding.description()
+  );
+  let message = if finding.kind == "vulnerability" {
+    message.red()
+  } else {
+    message.yellow()
+  };
+
+  writeln!(
+    output,
+    "  {} {}: {}",
+    Emoji("🛡️", "=>"),
+    finding.path,
+    message
+  )
+}
+
+fn rust_update(report: &Report) -> String {
+  format!(
+    "{} Update required to Rust {}",
+    Emoji("❗", "=>"),
+    report.rust_version.as_deref().unwrap_or_default()
+  )
+}
+
+/// Render a Rust version a worker is built with, and whether it is older than the expected version
+fn render_rust_version(
+  output: &mut String,
+  report: &Report,
+  label: &str,
+  rust_version: &RustVersion,
+) -> std::fmt::Result {
+  let update = if rust_version.update_required {
+    rust_update(report)
+  } else {
+    String::new()
+  };
+
+  writeln!(
+    output,
+    "    {} {} {} {}",
+    Emoji("🦀", &"=>".yellow().bold()),
+    label.yellow(),
+    rust_version.version.yellow(),
+    update.red()
+  )
+}
+
+/// Render the requirement of the SDK, with its locked version and its compliance with the expected version
+fn render_sdk_requirement(
+  output: &mut String,
+  report: &Report,
+  sdk: &SdkRequirement,
+) -> std::fmt::Result {
+  if let Some(error) = &sdk.lockfile_error {
+    writeln!(output, "    {} {}", Emoji("❗", "=>"), error.red())?;
+  }
+
+  let lock = match &sdk.locked {
+    Some(locked) if sdk.stale_lockfile => {
+      format!("(locked {}, stale {})", locked, sdk.lockfile).red()
+    }
+    Some(locked) => format!("(locked {})", locked).magenta(),
+    None => "".normal(),
+  };
+
+  let update = if sdk.update_required {
+    format!(
+      "{} Update required to version {}",
+      Emoji("❗", "=>"),
+      report.mcai_sdk_version.as_deref().unwrap_or_default()
+    )
+  } else {
+    String::new()
+  };
+
+  writeln!(
+    output,
+    "    {} {} {} {} {}",
+    Emoji("📦", &"=>".magenta().bold()),
+    "MCAI Worker SDK".magenta(),
+    sdk.requirement.magenta(),
+    lock,
+    update.red()
+  )
+}
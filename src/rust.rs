@@ -0,0 +1,43 @@
+use crate::config::{FileKind, RepoConfig};
+use toml::Value;
+
+// official Docker images of Rust
+static RUST_IMAGES: &[&str] = &["rust", "library/rust"];
+
+/// Toolchain declared by a `rust-toolchain` or `rust-toolchain.toml` file
+pub struct Toolchain {
+  pub path: String,
+  // channel of the toolchain, like `1.60.0`, `stable` or `nightly-2021-06-01`
+  pub channel: String,
+}
+
+/// Get the toolchains declared by the fetched toolchain files of a repository
+pub fn toolchains(repo: &RepoConfig) -> Vec<Toolchain> {
+  repo
+    .fetched_files(FileKind::Toolchain)
+    .into_iter()
+    .filter_map(|(path, content)| channel(content).map(|channel| Toolchain { path, channel }))
+    .collect()
+}
+
+/// Get the channel of a toolchain file, in the TOML format or the legacy one only containing the channel
+fn channel(content: &str) -> Option<String> {
+  if let Ok(toolchain) = toml::from_str::<Value>(content) {
+    return toolchain
+      .get("toolchain")
+      .and_then(|toolchain| toolchain.get("channel"))
+      .and_then(|channel| channel.as_str())
+      .map(|channel| channel.to_string());
+  }
+
+  content
+    .lines()
+    .map(|line| line.trim())
+    .find(|line| !line.is_empty())
+    .map(|line| line.to_string())
+}
+
+/// Check whether a Docker image is an official Rust image
+pub fn is_rust_image(image: &str) -> bool {
+  RUST_IMAGES.contains(&image)
+}
@@ -0,0 +1,157 @@
+use semver::{Version, VersionReq};
+
+// lags at or above which `check` warns or fails, `never` disabling the threshold
+pub static THRESHOLDS: &[&str] = &["patch", "minor", "major", "never"];
+
+/// How far a version is behind the expected one, given by the first component which differs
+#[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd, Serialize)]
+pub enum Lag {
+  #[serde(rename = "patch")]
+  Patch,
+  #[serde(rename = "minor")]
+  Minor,
+  #[serde(rename = "major")]
+  Major,
+}
+
+impl Lag {
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      Lag::Patch => "patch",
+      Lag::Minor => "minor",
+      Lag::Major => "major",
+    }
+  }
+}
+
+impl From<&str> for Lag {
+  fn from(value: &str) -> Self {
+    match value {
+      "patch" => Lag::Patch,
+      "minor" => Lag::Minor,
+      "major" => Lag::Major,
+      _ => panic!("Invalid lag"),
+    }
+  }
+}
+
+/// Get the lag of a version (a toolchain channel, an image tag, a `rust-version` or a locked version) behind the expected one.
+/// Versions without patch, like `1.60`, match any patch release.
+/// Versions which are not older, or which can not be compared (like `stable` or `nightly`), give `None`.
+pub fn lag(version: &str, expected: &Version) -> Option<Lag> {
+  let components = components(version)?;
+
+  let expected = [expected.major, expected.minor, expected.patch];
+  components
+    .iter()
+    .zip(&expected)
+    .zip(&[Lag::Major, Lag::Minor, Lag::Patch])
+    .find(|((component, expected), _)| component != expected)
+    .and_then(|((component, expected), lag)| (component < expected).then_some(*lag))
+}
+
+/// Get the lag of a requirement behind the expected version, `None` when the requirement allows it.
+/// The lag is given by the first version of the requirement, like `0.11` in `>=0.11, <1`.
+/// Requirements starting at a newer version are ahead and give `None`, the ones which can not be compared being incompatible.
+pub fn requirement_lag(
+  specifiers: &str,
+  requirement: &VersionReq,
+  expected: &Version,
+) -> Option<Lag> {
+  if requirement.matches(expected) {
+    return None;
+  }
+
+  let first_version = specifiers.trim_start_matches(|c: char| !c.is_ascii_digit());
+  let components = match components(first_version) {
+    Some(components) => components,
+    None => return Some(Lag::Major),
+  };
+
+  // upper bounds, like `<1.3`, exclude the expected version from their last component
+  if specifiers.trim_start().starts_with('<') {
+    let bound = [Lag::Major, Lag::Minor, Lag::Patch][components.len() - 1];
+    return Some(lag(first_version, expected).unwrap_or(bound));
+  }
+  lag(first_version, expected)
+}
+
+/// Get the leading numeric components of a version, like `[1, 60]` for `1.60-slim`
+fn components(version: &str) -> Option<Vec<u64>> {
+  let end = version
+    .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+    .unwrap_or(version.len());
+
+  let components: Vec<u64> = version[..end]
+    .trim_end_matches('.')
+    .split('.')
+    .map(|component| component.parse().ok())
+    .collect::<Option<Vec<u64>>>()?;
+  if components.len() > 3 {
+    return None;
+  }
+  Some(components)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::cargo;
+
+  #[test]
+  fn lag_behind_expected() {
+    let expected = Version::parse("1.60.2").unwrap();
+    let cases = vec![
+      ("0.60.2", Some(Lag::Major)),
+      ("1.59.9", Some(Lag::Minor)),
+      ("1.60.1", Some(Lag::Patch)),
+      ("1.60.2", None),
+      ("1.61.0", None),
+      ("2.0.0", None),
+      ("1.60", None),
+      ("1.59", Some(Lag::Minor)),
+      ("1", None),
+      ("1.59-slim-bullseye", Some(Lag::Minor)),
+      ("1.60.2.1", None),
+      ("stable", None),
+      ("nightly-2022-01-01", None),
+    ];
+
+    for (version, lag_) in cases {
+      assert_eq!(lag(version, &expected), lag_, "{}", version);
+    }
+  }
+
+  #[test]
+  fn requirement_lag_behind_expected() {
+    let expected = Version::parse("1.2.3").unwrap();
+    let cases = vec![
+      ("1.2", None),
+      ("^1.0", None),
+      (">=1.0", None),
+      (">=1.2.4", None),
+      (">=2.0", None),
+      (">1.2.3", None),
+      ("~1.3", None),
+      ("^0.11", Some(Lag::Major)),
+      (">=0.11, <1", Some(Lag::Major)),
+      ("~1.1", Some(Lag::Minor)),
+      (">=1.0, <1.2", Some(Lag::Minor)),
+      ("=1.2.2", Some(Lag::Patch)),
+      ("<1.2.3", Some(Lag::Patch)),
+      ("<1.2", Some(Lag::Minor)),
+      ("<1", Some(Lag::Major)),
+      ("<1.1.9", Some(Lag::Minor)),
+    ];
+
+    for (specifiers, lag_) in cases {
+      let requirement = cargo::requirement(specifiers).unwrap();
+      assert_eq!(
+        requirement_lag(specifiers, &requirement, &expected),
+        lag_,
+        "{}",
+        specifiers
+      );
+    }
+  }
+}